@@ -1,14 +1,14 @@
 -- Initial code is courtesy of to https://package.elm-lang.org/packages/juliusl/elm-ui-hexcolor/latest/Element-HexColor
 
 
-module ColorHelper exposing (colorForSessionType, computeCurrentColor, fromCSSHexToRGB, fromRGBToCSSHex)
+module ColorHelper exposing (colorForSessionType, fromCSSHexToRGB, fromRGBToCSSHex)
 
 import Bitwise
 import Dict exposing (Dict)
 import Hex
 import List
 import Themes exposing (Theme)
-import Types exposing (RGB(..), Seconds, SessionType(..))
+import Types exposing (RGB(..), SessionType(..))
 
 
 toStringWithZeroPadding : Int -> String
@@ -109,51 +109,3 @@ colorForSessionType sessionType theme =
         LongBreak ->
             fromCSSHexToRGB <| theme.colors.longRound
 
-
-computeCurrentColor : Seconds -> Seconds -> SessionType -> Theme -> RGB
-computeCurrentColor currentTime maxTime sessionType theme =
-    case sessionType of
-        Focus ->
-            let
-                remainingPercent =
-                    if maxTime /= 0 then
-                        toFloat (maxTime - currentTime) / toFloat maxTime
-
-                    else
-                        1
-
-                relativePercent =
-                    if maxTime /= 0 then
-                        (toFloat (maxTime - currentTime) - toFloat maxTime / 2) / (toFloat maxTime / 2)
-
-                    else
-                        1
-
-                ( startRed, startGreen, startBlue ) =
-                    case fromCSSHexToRGB theme.colors.focusRound of
-                        RGB r g b ->
-                            ( r, g, b )
-
-                ( middleRed, middleGreen, middleBlue ) =
-                    case fromCSSHexToRGB theme.colors.focusRoundMiddle of
-                        RGB r g b ->
-                            ( r, g, b )
-
-                ( endRed, endGreen, endBlue ) =
-                    case fromCSSHexToRGB theme.colors.focusRoundEnd of
-                        RGB r g b ->
-                            ( r, g, b )
-            in
-            if remainingPercent > 0.5 then
-                RGB
-                    (toFloat middleRed + (relativePercent * toFloat (startRed - middleRed)) |> round)
-                    (toFloat middleGreen + (relativePercent * toFloat (startGreen - middleGreen)) |> round)
-                    (toFloat middleBlue + (relativePercent * toFloat (startBlue - middleBlue)) |> round)
-
-            else
-                RGB (toFloat endRed + ((1 + relativePercent) * toFloat (middleRed - endRed)) |> round)
-                    (toFloat endGreen + ((1 + relativePercent) * toFloat (middleGreen - endGreen)) |> round)
-                    (toFloat endBlue + ((1 + relativePercent) * toFloat (middleBlue - endBlue)) |> round)
-
-        s ->
-            colorForSessionType s theme
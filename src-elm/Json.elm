@@ -93,14 +93,20 @@ soundMessageEncoder soundMessageValue =
 configEncoder : Config -> Encode.Value
 configEncoder config =
     Encode.object
-        [ ( "alwaysOnTop", Encode.bool config.alwaysOnTop )
+        [ ( "activeProfile"
+          , config.activeProfile
+                |> Maybe.map Encode.string
+                |> Maybe.withDefault Encode.null
+          )
+        , ( "alwaysOnTop", Encode.bool config.alwaysOnTop )
         , ( "autoQuit"
           , config.autoQuit
                 |> Maybe.map (\c -> Encode.string (sessionTypeToString c))
                 |> Maybe.withDefault Encode.null
           )
-        , ( "autoStartBreakTimer", Encode.bool config.autoStartBreakTimer )
+        , ( "autoStartLongBreakTimer", Encode.bool config.autoStartLongBreakTimer )
         , ( "autoStartOnAppStartup", Encode.bool config.autoStartOnAppStartup )
+        , ( "autoStartShortBreakTimer", Encode.bool config.autoStartShortBreakTimer )
         , ( "autoStartWorkTimer", Encode.bool config.autoStartWorkTimer )
         , ( "defaultFocusLabel", Encode.string config.defaultFocusLabel )
         , ( "defaultLongBreakLabel", Encode.string config.defaultLongBreakLabel )
@@ -145,26 +151,30 @@ configEncoder config =
 configDecoder : Decode.Decoder Config
 configDecoder =
     Decode.succeed Config
+        |> Pipe.optional "active_profile" (Decode.maybe Decode.string) Nothing
         |> Pipe.required "always_on_top" Decode.bool
         |> Pipe.optional "auto_quit" (Decode.maybe sessionTypeDecoder) Nothing
-        |> Pipe.required "auto_start_break_timer" Decode.bool
+        |> Pipe.required "auto_start_long_break_timer" Decode.bool
         |> Pipe.required "auto_start_on_app_startup" Decode.bool
+        |> Pipe.required "auto_start_short_break_timer" Decode.bool
         |> Pipe.required "auto_start_work_timer" Decode.bool
         |> Pipe.required "default_focus_label" Decode.string
         |> Pipe.required "default_long_break_label" Decode.string
         |> Pipe.required "default_short_break_label" Decode.string
         |> Pipe.required "desktop_notifications" Decode.bool
         |> Pipe.optional "focus_audio" (Decode.maybe Decode.string) Nothing
-        |> Pipe.required "focus_duration" Decode.int
+        |> Pipe.required "focus_duration" secondsDecoder
         |> Pipe.optional "long_break_audio" (Decode.maybe Decode.string) Nothing
-        |> Pipe.required "long_break_duration" Decode.int
+        |> Pipe.required "long_break_duration" secondsDecoder
         |> Pipe.required "max_round_number" Decode.int
-        |> Pipe.required "max_session_duration" Decode.int
+        |> Pipe.required "max_session_duration" secondsDecoder
         |> Pipe.required "minimize_to_tray" Decode.bool
         |> Pipe.required "minimize_to_tray_on_close" Decode.bool
         |> Pipe.required "muted" Decode.bool
+        -- Only the names are needed to switch between profiles
+        |> Pipe.optional "profiles" (Decode.keyValuePairs Decode.value |> Decode.map (List.map Tuple.first)) []
         |> Pipe.optional "short_break_audio" (Decode.maybe Decode.string) Nothing
-        |> Pipe.required "short_break_duration" Decode.int
+        |> Pipe.required "short_break_duration" secondsDecoder
         |> Pipe.required "start_minimized" Decode.bool
         |> Pipe.required "system_startup_auto_start" Decode.bool
         |> Pipe.required "theme" Decode.string
@@ -173,9 +183,18 @@ configDecoder =
         |> Pipe.optional "volume" (Decode.maybe Decode.int) Nothing
 
 
+-- Durations can have a fractional part, the settings only deal with whole seconds
+
+
+secondsDecoder : Decode.Decoder Int
+secondsDecoder =
+    Decode.map round Decode.float
+
+
 initDataDecoder : Decode.Decoder InitData
 initDataDecoder =
     Decode.succeed InitData
+        |> Pipe.required "active_theme" Decode.string
         |> Pipe.required "config" configDecoder
         |> Pipe.required "pomodoro_state" rustStateDecoder
         |> Pipe.required "themes" themesDecoder
@@ -184,8 +203,11 @@ initDataDecoder =
 rustStateDecoder : Decode.Decoder PomodoroState
 rustStateDecoder =
     Decode.succeed PomodoroState
+        |> Pipe.required "color" Decode.string
         |> Pipe.required "current_session" rustSessionDecoder
         |> Pipe.required "current_work_round_number" Decode.int
+        |> Pipe.optional "done_for_the_day" Decode.bool False
+        |> Pipe.optional "ended_sessions" Decode.int 0
 
 
 sessionTypeFromStringDecoder : String -> Decode.Decoder SessionType
@@ -234,8 +256,12 @@ rustSessionDecoder : Decode.Decoder PomodoroSession
 rustSessionDecoder =
     Decode.succeed PomodoroSession
         |> Pipe.required "current_time" Decode.int
+        |> Pipe.optional "duration" (Decode.map Just Decode.int) Nothing
+        |> Pipe.optional "extra_time" Decode.int 0
+        |> Pipe.optional "flow" Decode.bool False
         |> Pipe.optional "label" (Decode.maybe Decode.string) Nothing
         |> Pipe.required "session_type" sessionTypeDecoder
+        |> Pipe.optional "start_delay" Decode.int 0
         |> Pipe.required "status" sessionStatusDecoder
 
 
@@ -245,4 +271,6 @@ externalMessageDecoder =
         [ rustStateDecoder |> Decode.map RustStateMsg
         , initDataDecoder |> Decode.map InitDataMsg
         , Decode.map2 SoundFilePath (Decode.field "session_type" sessionTypeDecoder) (Decode.field "file_path" Decode.string)
+        , Decode.field "theme_name" Decode.string |> Decode.map ThemeChangedMsg
+        , Decode.field "config_changed" configDecoder |> Decode.map ConfigChangedMsg
         ]
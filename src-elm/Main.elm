@@ -1,14 +1,14 @@
 port module Main exposing (Flags, main)
 
 import Browser
-import ColorHelper exposing (colorForSessionType, computeCurrentColor, fromCSSHexToRGB, fromRGBToCSSHex)
+import ColorHelper exposing (colorForSessionType, fromCSSHexToRGB, fromRGBToCSSHex)
 import Html exposing (Html, div)
 import Html.Attributes exposing (id)
 import Json exposing (configEncoder, currentStateEncoder, elmMessageBuilder, elmMessageEncoder, externalMessageDecoder, sessionTypeDecoder, soundMessageEncoder)
 import Json.Decode as Decode
 import Json.Encode as Encode
 import ListWithCurrent exposing (ListWithCurrent(..))
-import Themes exposing (ThemeColors, pomodorolmTheme)
+import Themes exposing (Theme, ThemeColors, pomodorolmTheme)
 import TimeHelper exposing (getCurrentMaxTime)
 import Types
     exposing
@@ -18,7 +18,6 @@ import Types
         , Model
         , Msg(..)
         , Notification
-        , RGB(..)
         , ResetType(..)
         , Seconds
         , SessionStatus(..)
@@ -48,8 +47,9 @@ type alias Flags =
     { alwaysOnTop : Bool
     , appVersion : String
     , autoQuit : Maybe String
-    , autoStartBreakTimer : Bool
+    , autoStartLongBreakTimer : Bool
     , autoStartOnAppStartup : Bool
+    , autoStartShortBreakTimer : Bool
     , autoStartWorkTimer : Bool
     , defaultFocusLabel : String
     , defaultShortBreakLabel : String
@@ -96,7 +96,8 @@ init flags =
     in
     ( { appVersion = flags.appVersion
       , config =
-            { alwaysOnTop = flags.alwaysOnTop
+            { activeProfile = Nothing
+            , alwaysOnTop = flags.alwaysOnTop
             , autoQuit =
                 flags.autoQuit
                     |> Maybe.andThen
@@ -104,8 +105,9 @@ init flags =
                             Decode.decodeString sessionTypeDecoder v
                                 |> Result.toMaybe
                         )
-            , autoStartBreakTimer = flags.autoStartBreakTimer
+            , autoStartLongBreakTimer = flags.autoStartLongBreakTimer
             , autoStartOnAppStartup = flags.autoStartOnAppStartup
+            , autoStartShortBreakTimer = flags.autoStartShortBreakTimer
             , autoStartWorkTimer = flags.autoStartWorkTimer
             , defaultFocusLabel = flags.defaultFocusLabel
             , defaultLongBreakLabel = flags.defaultLongBreakLabel
@@ -120,6 +122,7 @@ init flags =
             , minimizeToTray = flags.minimizeToTray
             , minimizeToTrayOnClose = flags.minimizeToTrayOnClose
             , muted = flags.muted
+            , profiles = []
             , shortBreakAudio = Nothing
             , shortBreakDuration = flags.shortBreakDuration
             , startMinimized = flags.startMinimized
@@ -168,33 +171,18 @@ update msg ({ config } as model) =
 
         ChangeTheme theme ->
             let
-                currentState =
-                    model.currentState
-
-                newState =
-                    { currentState
-                        | color =
-                            fromRGBToCSSHex
-                                (model.pomodoroState
-                                    |> Maybe.map (\state -> colorForSessionType state.currentSession.sessionType theme)
-                                    |> Maybe.withDefault (fromCSSHexToRGB <| theme.colors.focusRound)
-                                )
-                    }
-
                 newConfig =
                     { config
                         | theme = theme.name |> String.toLower
                     }
+
+                ( newModel, themeCmd ) =
+                    applyTheme theme { model | config = newConfig }
             in
-            ( { model
-                | config = newConfig
-                , currentState = newState
-                , theme = theme
-              }
+            ( newModel
             , Cmd.batch
-                [ setThemeColors theme.colors
+                [ themeCmd
                 , sendMessageFromElm (elmMessageBuilder "update_config" newConfig configEncoder)
-                , sendMessageFromElm (elmMessageBuilder "update_current_state" newState currentStateEncoder)
                 ]
             )
 
@@ -227,13 +215,13 @@ update msg ({ config } as model) =
                 updatedThemes =
                     c.themes
                         |> ListWithCurrent.fromList
-                        |> ListWithCurrent.setCurrentByPredicate (\t -> (t.name |> String.toLower) == c.config.theme)
+                        |> ListWithCurrent.setCurrentByPredicate (\t -> (t.name |> String.toLower) == c.activeTheme)
 
                 newThemes =
                     case ListWithCurrent.getCurrent updatedThemes of
                         Just theme ->
                             -- We found a theme with the same name than in the config: everything's fine
-                            if (theme.name |> String.toLower) == (c.config.theme |> String.toLower) then
+                            if (theme.name |> String.toLower) == (c.activeTheme |> String.toLower) then
                                 updatedThemes
 
                             else
@@ -258,7 +246,7 @@ update msg ({ config } as model) =
                         Just currentTheme ->
                             let
                                 ( updatedModel, updatedCmd ) =
-                                    update (ChangeTheme currentTheme) newModel
+                                    applyTheme currentTheme newModel
                             in
                             ( updatedModel, Cmd.batch [ updatedCmd, newCmd ] )
 
@@ -278,39 +266,10 @@ update msg ({ config } as model) =
 
         ProcessExternalMessage (RustStateMsg pomodoroState) ->
             let
-                getNotification : String -> String -> String -> Seconds -> RGB -> Notification
-                getNotification title body name duration (RGB r g b) =
-                    let
-                        minutes =
-                            (duration |> toFloat) / 60 |> round
-                    in
-                    { title = title
-                    , body =
-                        "Start a "
-                            ++ String.fromInt minutes
-                            ++ " minute"
-                            ++ (if minutes > 1 then
-                                    "s"
-
-                                else
-                                    ""
-                               )
-                            ++ " "
-                            ++ body
-                    , name = name
-                    , red = r
-                    , green = g
-                    , blue = b
-                    }
-
-                getCmds : Config -> String -> String -> String -> String -> Seconds -> RGB -> Bool -> List (Cmd Msg)
-                getCmds { desktopNotifications, muted } soundName title body name duration rgb quit =
-                    [ if desktopNotifications then
-                        notify <| getNotification title body name duration rgb
-
-                      else
-                        Cmd.none
-                    , if muted then
+                -- The notifications are sent by Rust
+                getCmds : Config -> String -> Bool -> List (Cmd Msg)
+                getCmds { muted } soundName quit =
+                    [ if muted then
                         if quit then
                             sendMessageFromElm (elmMessageEncoder { name = "quit", value = Nothing })
 
@@ -324,8 +283,9 @@ update msg ({ config } as model) =
                 maxTime =
                     getCurrentMaxTime config pomodoroState
 
+                -- The color is computed by Rust so that the window and the tray icon always agree
                 currentColor =
-                    computeCurrentColor pomodoroState.currentSession.currentTime maxTime pomodoroState.currentSession.sessionType model.theme
+                    fromCSSHexToRGB pomodoroState.color
 
                 percent =
                     if maxTime /= 0 then
@@ -346,23 +306,23 @@ update msg ({ config } as model) =
                     model.pomodoroState
                         |> Maybe.map
                             (\state ->
-                                -- If we’ve changed the session type
-                                if state.currentSession.sessionType /= pomodoroState.currentSession.sessionType then
+                                -- If a session ended, even if the next one is of the same type
+                                if state.endedSessions /= pomodoroState.endedSessions then
+                                    let
+                                        -- Reaching the daily focus target has its own sound
+                                        soundFor : String -> String
+                                        soundFor soundName =
+                                            if pomodoroState.doneForTheDay && not state.doneForTheDay then
+                                                "audio-daily-target"
+
+                                            else
+                                                soundName
+                                    in
                                     case pomodoroState.currentSession.sessionType of
                                         Focus ->
                                             getCmds
                                                 config
-                                                "audio-work"
-                                                (if state.currentSession.sessionType == ShortBreak then
-                                                    "Short break completed"
-
-                                                 else
-                                                    "Long break completed"
-                                                )
-                                                "focus round"
-                                                "start_focus"
-                                                model.config.focusDuration
-                                                currentColor
+                                                (soundFor "audio-work")
                                                 ((config.autoQuit == Just ShortBreak && state.currentSession.sessionType == ShortBreak)
                                                     || (config.autoQuit == Just LongBreak && state.currentSession.sessionType == LongBreak)
                                                 )
@@ -370,23 +330,13 @@ update msg ({ config } as model) =
                                         LongBreak ->
                                             getCmds
                                                 config
-                                                "audio-long-break"
-                                                "Focus round completed"
-                                                "long break"
-                                                "start_long_break"
-                                                model.config.longBreakDuration
-                                                currentColor
+                                                (soundFor "audio-long-break")
                                                 False
 
                                         ShortBreak ->
                                             getCmds
                                                 config
-                                                "audio-short-break"
-                                                "Focus round completed"
-                                                "short break"
-                                                "start_short_break"
-                                                model.config.shortBreakDuration
-                                                currentColor
+                                                (soundFor "audio-short-break")
                                                 (config.autoQuit == Just Focus)
 
                                 else
@@ -420,6 +370,24 @@ update msg ({ config } as model) =
             , sendMessageFromElm (elmMessageBuilder "update_config" newConfig configEncoder)
             )
 
+        ProcessExternalMessage (ConfigChangedMsg newConfig) ->
+            -- The theme is managed by the theme messages
+            ( { model | config = { newConfig | theme = config.theme } }, Cmd.none )
+
+        ProcessExternalMessage (ThemeChangedMsg themeName) ->
+            case
+                model.themes
+                    |> ListWithCurrent.toList
+                    |> List.filter (\t -> (t.name |> String.toLower) == (themeName |> String.toLower))
+                    |> List.head
+            of
+                Just theme ->
+                    -- Only shown, the theme of the config stays the one chosen by the user
+                    applyTheme theme model
+
+                Nothing ->
+                    ( model, Cmd.none )
+
         Reset resetType ->
             let
                 currentState =
@@ -492,8 +460,25 @@ update msg ({ config } as model) =
             ( { model | volumeSliderHidden = False }, Cmd.none )
 
         SkipCurrentRound ->
+            let
+                -- A flow session that went on for a while is stopped instead, and followed by its break
+                flowStarted =
+                    model.pomodoroState
+                        |> Maybe.map (\state -> state.currentSession.flow && state.currentSession.currentTime > 0)
+                        |> Maybe.withDefault False
+            in
             ( model
-            , sendMessageFromElm (elmMessageEncoder { name = "skip", value = Nothing })
+            , sendMessageFromElm
+                (elmMessageEncoder
+                    { name =
+                        if flowStarted then
+                            "stop"
+
+                        else
+                            "skip"
+                    , value = Nothing
+                    }
+                )
             )
 
         ToggleDrawer ->
@@ -565,13 +550,7 @@ update msg ({ config } as model) =
                                         getCurrentMaxTime config state
 
                                     currentState =
-                                        { color =
-                                            fromRGBToCSSHex <|
-                                                computeCurrentColor
-                                                    state.currentSession.currentTime
-                                                    (getCurrentMaxTime config state)
-                                                    state.currentSession.sessionType
-                                                    model.theme
+                                        { color = state.color
                                         , percentage =
                                             if maxTime /= 0 then
                                                 toFloat (maxTime - state.currentSession.currentTime) / toFloat maxTime
@@ -595,12 +574,7 @@ update msg ({ config } as model) =
                                         getCurrentMaxTime config state
 
                                     currentState =
-                                        { color =
-                                            fromRGBToCSSHex <|
-                                                computeCurrentColor state.currentSession.currentTime
-                                                    (getCurrentMaxTime config state)
-                                                    state.currentSession.sessionType
-                                                    model.theme
+                                        { color = state.color
                                         , percentage =
                                             if maxTime /= 0 then
                                                 toFloat (maxTime - state.currentSession.currentTime) / toFloat maxTime
@@ -645,6 +619,16 @@ update msg ({ config } as model) =
 
                 newConfig =
                     case settingType of
+                        ActiveProfile profileName ->
+                            { config
+                                | activeProfile =
+                                    if profileName == "" then
+                                        Nothing
+
+                                    else
+                                        Just profileName
+                            }
+
                         AutoQuit sessionTypeString ->
                             { config | autoQuit = sessionTypeFromString sessionTypeString }
 
@@ -686,8 +670,11 @@ update msg ({ config } as model) =
                                 AlwaysOnTop ->
                                     { config | alwaysOnTop = not config.alwaysOnTop }
 
-                                AutoStartBreakTimer ->
-                                    { config | autoStartBreakTimer = not config.autoStartBreakTimer }
+                                AutoStartLongBreakTimer ->
+                                    { config | autoStartLongBreakTimer = not config.autoStartLongBreakTimer }
+
+                                AutoStartShortBreakTimer ->
+                                    { config | autoStartShortBreakTimer = not config.autoStartShortBreakTimer }
 
                                 AutoStartWorkTimer ->
                                     { config | autoStartWorkTimer = not config.autoStartWorkTimer }
@@ -741,6 +728,36 @@ update msg ({ config } as model) =
             )
 
 
+-- Shows the colors of the theme, without saving it in the config
+
+
+applyTheme : Theme -> Model -> ( Model, Cmd Msg )
+applyTheme theme model =
+    let
+        currentState =
+            model.currentState
+
+        newState =
+            { currentState
+                | color =
+                    fromRGBToCSSHex
+                        (model.pomodoroState
+                            |> Maybe.map (\state -> colorForSessionType state.currentSession.sessionType theme)
+                            |> Maybe.withDefault (fromCSSHexToRGB <| theme.colors.focusRound)
+                        )
+            }
+    in
+    ( { model
+        | currentState = newState
+        , theme = theme
+      }
+    , Cmd.batch
+        [ setThemeColors theme.colors
+        , sendMessageFromElm (elmMessageBuilder "update_current_state" newState currentStateEncoder)
+        ]
+    )
+
+
 view : Model -> Html Msg
 view model =
     div [ id "app" ]
@@ -802,7 +819,4 @@ port minimizeWindow : () -> Cmd msg
 port hideWindow : () -> Cmd msg
 
 
-port notify : Notification -> Cmd msg
-
-
 port setThemeColors : ThemeColors -> Cmd msg
@@ -5,12 +5,21 @@ import Types exposing (Config, PomodoroState, Seconds, SessionType(..))
 
 getCurrentMaxTime : Config -> PomodoroState -> Seconds
 getCurrentMaxTime config state =
-    case state.currentSession.sessionType of
-        Focus ->
-            config.focusDuration
+    case state.currentSession.duration of
+        -- Computed by Rust, for the steps of the custom sequences
+        Just duration ->
+            duration
 
-        LongBreak ->
-            config.longBreakDuration
+        Nothing ->
+            -- The session may have been extended
+            state.currentSession.extraTime
+                + (case state.currentSession.sessionType of
+                    Focus ->
+                        config.focusDuration
 
-        ShortBreak ->
-            config.shortBreakDuration
+                    LongBreak ->
+                        config.longBreakDuration
+
+                    ShortBreak ->
+                        config.shortBreakDuration
+                  )
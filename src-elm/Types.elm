@@ -7,7 +7,6 @@ module Types exposing
     , InitData
     , Model
     , Msg(..)
-    , Notification
     , PomodoroSession
     , PomodoroState
     , RGB(..)
@@ -94,10 +93,12 @@ type alias SoundMessageValue =
 
 
 type alias Config =
-    { alwaysOnTop : Bool
+    { activeProfile : Maybe String
+    , alwaysOnTop : Bool
     , autoQuit : Maybe SessionType
-    , autoStartBreakTimer : Bool
+    , autoStartLongBreakTimer : Bool
     , autoStartOnAppStartup : Bool
+    , autoStartShortBreakTimer : Bool
     , autoStartWorkTimer : Bool
     , defaultFocusLabel : String
     , defaultLongBreakLabel : String
@@ -112,6 +113,7 @@ type alias Config =
     , minimizeToTray : Bool
     , minimizeToTrayOnClose : Bool
     , muted : Bool
+    , profiles : List String
     , shortBreakAudio : Maybe String
     , shortBreakDuration : Seconds
     , startMinimized : Bool
@@ -124,7 +126,8 @@ type alias Config =
 
 
 type alias InitData =
-    { config : Config
+    { activeTheme : String
+    , config : Config
     , pomodoroState : PomodoroState
     , themes : List Theme
     }
@@ -138,16 +141,6 @@ type alias CurrentState =
     }
 
 
-type alias Notification =
-    { body : String
-    , title : String
-    , name : String
-    , red : Int
-    , green : Int
-    , blue : Int
-    }
-
-
 type SessionType
     = Focus
     | ShortBreak
@@ -171,8 +164,9 @@ type SettingTab
 
 type Setting
     = AlwaysOnTop
-    | AutoStartBreakTimer
+    | AutoStartLongBreakTimer
     | AutoStartOnAppStartup
+    | AutoStartShortBreakTimer
     | AutoStartWorkTimer
     | DesktopNotifications
     | MinimizeToTray
@@ -192,7 +186,8 @@ type alias Defaults =
 
 
 type SettingType
-    = AutoQuit String
+    = ActiveProfile String
+    | AutoQuit String
     | FocusTime String
     | Label SessionType String
     | LongBreakTime String
@@ -205,15 +200,22 @@ type SettingType
 
 type alias PomodoroSession =
     { currentTime : Seconds
+    , duration : Maybe Seconds
+    , extraTime : Seconds
+    , flow : Bool
     , label : Maybe String
     , sessionType : SessionType
+    , startDelay : Seconds
     , status : SessionStatus
     }
 
 
 type alias PomodoroState =
-    { currentSession : PomodoroSession
+    { color : String
+    , currentSession : PomodoroSession
     , currentWorkRoundNumber : Int
+    , doneForTheDay : Bool
+    , endedSessions : Int
     }
 
 
@@ -221,6 +223,8 @@ type ExternalMessage
     = RustStateMsg PomodoroState
     | InitDataMsg InitData
     | SoundFilePath SessionType String
+    | ThemeChangedMsg String
+    | ConfigChangedMsg Config
 
 
 sessionTypeToString : SessionType -> String
@@ -352,6 +352,29 @@ timerSettingView model =
             [ class "drawer-heading"
             ]
             [ text "Timer" ]
+        , if List.isEmpty model.config.profiles then
+            text ""
+
+          else
+            div
+                [ class "setting-wrapper"
+                ]
+                [ p [ class "setting-title", style "margin-top" "0.3rem" ] [ text "Profile" ]
+                , div
+                    []
+                    [ select [ onInput (ActiveProfile >> UpdateSetting) ]
+                        (option [ value "", selected (model.config.activeProfile == Nothing) ] [ text "Default" ]
+                            :: (model.config.profiles
+                                    |> List.map
+                                        (\profileName ->
+                                            option
+                                                [ value profileName, selected (model.config.activeProfile == Just profileName) ]
+                                                [ text profileName ]
+                                        )
+                               )
+                        )
+                    ]
+                ]
         , div
             [ class "setting-wrapper"
             ]
@@ -543,7 +566,8 @@ settingsSettingView { config } =
         , settingWrapperToggle "Always On Top" (UpdateSetting <| Toggle AlwaysOnTop) config.alwaysOnTop
         , settingWrapperToggle "Auto-start Work Timer after Break" (UpdateSetting <| Toggle AutoStartWorkTimer) config.autoStartWorkTimer
         , settingWrapperToggle "Auto-start Work Timer at app startup" (UpdateSetting <| Toggle AutoStartOnAppStartup) config.autoStartOnAppStartup
-        , settingWrapperToggle "Auto-start Break Timer after Work" (UpdateSetting <| Toggle AutoStartBreakTimer) config.autoStartBreakTimer
+        , settingWrapperToggle "Auto-start Short Break Timer after Work" (UpdateSetting <| Toggle AutoStartShortBreakTimer) config.autoStartShortBreakTimer
+        , settingWrapperToggle "Auto-start Long Break Timer after Work" (UpdateSetting <| Toggle AutoStartLongBreakTimer) config.autoStartLongBreakTimer
         , settingWrapperToggle "Auto-start the app on system startup" (UpdateSetting <| Toggle SystemStartupAutoStart) config.systemStartupAutoStart
         , div
             [ class "setting-wrapper"
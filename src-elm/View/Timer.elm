@@ -1,18 +1,16 @@
 module View.Timer exposing (timerView)
 
-import ColorHelper exposing (computeCurrentColor)
 import Html exposing (Html, div, input, p, section, text)
 import Html.Attributes exposing (class, id, style, title, type_, value)
 import Html.Events exposing (onClick, onInput, onMouseLeave, onMouseOver)
 import Svg exposing (path, svg)
 import Svg.Attributes as SvgAttr
-import Themes exposing (Theme)
 import TimeHelper exposing (getCurrentMaxTime)
 import Types exposing (Model, Msg(..), RGB(..), ResetType(..), Seconds, SessionStatus(..), SessionType(..))
 
 
-dialView : SessionType -> Seconds -> Seconds -> Float -> Theme -> String -> String -> String -> Html Msg
-dialView sessionType currentTime maxTime maxStrokeDasharray theme focusLabel shortBreakLabel longBreakLabel =
+dialView : SessionType -> Maybe String -> Bool -> Seconds -> Seconds -> Seconds -> Float -> RGB -> String -> String -> String -> Html Msg
+dialView sessionType stepName flow startDelay currentTime maxTime maxStrokeDasharray currentColor focusLabel shortBreakLabel longBreakLabel =
     let
         remainingPercent =
             if maxTime /= 0 then
@@ -28,24 +26,39 @@ dialView sessionType currentTime maxTime maxStrokeDasharray theme focusLabel sho
             "rgb(" ++ String.fromInt r ++ ", " ++ String.fromInt g ++ ", " ++ String.fromInt b ++ ")"
 
         color =
-            colorToHtmlRgbString <| computeCurrentColor currentTime maxTime sessionType theme
+            colorToHtmlRgbString currentColor
     in
     div [ class "dial-wrapper" ]
         [ p [ class "dial-time" ]
-            [ text <| secondsToString (maxTime - currentTime) ]
+            [ text <|
+                -- Waiting for the auto-start delay
+                if startDelay > 0 then
+                    "Starts in " ++ String.fromInt startDelay
+
+                else if flow then
+                    -- Flow sessions count up
+                    secondsToString currentTime
+
+                else
+                    secondsToString (maxTime - currentTime)
+            ]
         , p [ class "dial-label", style "color" color ]
             [ input
                 [ type_ "text"
                 , value
-                    (case sessionType of
-                        Focus ->
-                            focusLabel
+                    -- The steps of the custom sequences are named after their step
+                    (Maybe.withDefault
+                        (case sessionType of
+                            Focus ->
+                                focusLabel
 
-                        ShortBreak ->
-                            shortBreakLabel
+                            ShortBreak ->
+                                shortBreakLabel
 
-                        LongBreak ->
-                            longBreakLabel
+                            LongBreak ->
+                                longBreakLabel
+                        )
+                        stepName
                     )
                 , style "color" color
                 , onInput <| UpdateLabel sessionType
@@ -101,12 +114,12 @@ dialView sessionType currentTime maxTime maxStrokeDasharray theme focusLabel sho
 
 
 timerView : Model -> Html Msg
-timerView ({ config, strokeDasharray, theme, pomodoroState, focusLabel, shortBreakLabel, longBreakLabel } as model) =
+timerView ({ config, currentColor, strokeDasharray, pomodoroState, focusLabel, shortBreakLabel, longBreakLabel } as model) =
     pomodoroState
         |> Maybe.map
             (\state ->
                 div [ class "timer-wrapper" ]
-                    [ dialView state.currentSession.sessionType state.currentSession.currentTime (getCurrentMaxTime config state) strokeDasharray theme focusLabel shortBreakLabel longBreakLabel
+                    [ dialView state.currentSession.sessionType state.currentSession.label state.currentSession.flow state.currentSession.startDelay state.currentSession.currentTime (getCurrentMaxTime config state) strokeDasharray currentColor focusLabel shortBreakLabel longBreakLabel
                     , playPauseView state.currentSession.status
                     , footerView model
                     ]
@@ -1,22 +1,307 @@
 extern crate dirs;
-use crate::config::Config;
+use crate::config::{self, Config, ConfigSource};
+use crate::control;
+use crate::duration;
+use crate::theme::JsonTheme;
+use crate::theme_import::{self, ThemeFormat};
+use clap::ValueEnum;
 
 use std::fs;
-use std::path::Path;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::time::{Duration, SystemTime};
 use tokio::time::interval;
 
-pub fn run(config_dir_name: &str) {
-    let config_dir = dirs::config_dir()
-        .expect("Error while getting the config directory")
-        .join(config_dir_name);
-
-    let config =
-        Config::get_or_create_from_disk(&config_dir, None).expect("Unable to get config file");
+pub fn run(config_source: &ConfigSource) {
+    let config = match config_source.load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!(
+                "Unable to load the config file {}: {e}",
+                config_source.config_file.display()
+            );
+            std::process::exit(1);
+        }
+    };
 
     // Initialize the Tokio runtime
     let rt = tokio::runtime::Runtime::new().unwrap();
-    rt.block_on(run_pomodoro_checker(config));
+    rt.block_on(run_pomodoro_checker(config.with_active_profile()));
+}
+
+/// The config as stored in the file or, if `effective`, the one the app runs with: overrides and
+/// active profile applied.
+pub fn show_config(
+    config_source: &ConfigSource,
+    effective: bool,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let config = if effective {
+        config_source.load()?.with_active_profile()
+    } else {
+        config_source.load_file()?
+    };
+
+    Ok(toml::to_string(&config)?)
+}
+
+/// Value of `setting` in the config file, empty if it isn't set.
+pub fn get_setting(
+    config_source: &ConfigSource,
+    setting: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if !config::settings_names().iter().any(|name| name == setting) {
+        return Err(format!("Unknown setting `{setting}`.").into());
+    }
+
+    let settings = toml::Table::try_from(config_source.load_file()?)?;
+
+    Ok(settings.get(setting).map(format_value).unwrap_or_default())
+}
+
+/// All the settings of the config file.
+pub fn list_settings(
+    config_source: &ConfigSource,
+) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+    let settings = toml::Table::try_from(config_source.load_file()?)?;
+
+    let mut names = config::settings_names();
+    names.sort();
+
+    Ok(names
+        .into_iter()
+        .map(|name| {
+            let value = match settings.get(&name) {
+                Some(value) => format_value(value),
+                None => "(not set)".to_string(),
+            };
+
+            (name, value)
+        })
+        .collect())
+}
+
+/// Sets `setting` in the config file, durations can be given like `25m` or `1h30m`.
+pub fn set_setting(
+    config_source: &ConfigSource,
+    setting: &str,
+    value: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut settings = toml::Table::try_from(config_source.load_file()?)?;
+    settings.insert(setting.to_string(), config::parse_setting(setting, value)?);
+
+    let config: Config = settings
+        .try_into()
+        .map_err(|e| format!("Invalid value `{value}` for `{setting}`: {e}"))?;
+
+    Ok(config.save(&config_source.config_file)?)
+}
+
+/// Gives back its default value to `setting`, or to all of them if `None` except the tables and
+/// the lists, like the profiles, the notification templates or the steps of the sequence.
+pub fn reset_settings(
+    config_source: &ConfigSource,
+    setting: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config: Config = match setting {
+        None => {
+            let mut settings = toml::Table::try_from(Config::default())?;
+            // They are written by hand and would be lost for good
+            settings.extend(
+                toml::Table::try_from(config_source.load_file()?)?
+                    .into_iter()
+                    .filter(|(_, value)| value.is_table() || value.is_array()),
+            );
+
+            settings.try_into()?
+        }
+        Some(setting) => {
+            if !config::settings_names().iter().any(|name| name == setting) {
+                return Err(format!("Unknown setting `{setting}`.").into());
+            }
+
+            let mut settings = toml::Table::try_from(config_source.load_file()?)?;
+            match toml::Table::try_from(Config::default())?.remove(setting) {
+                Some(default) => settings.insert(setting.to_string(), default),
+                // Unset by default
+                None => settings.remove(setting),
+            };
+
+            settings.try_into()?
+        }
+    };
+
+    Ok(config.save(&config_source.config_file)?)
+}
+
+/// Opens the config file in `$VISUAL` or `$EDITOR`, and only saves it if it's valid.
+pub fn edit_config(config_source: &ConfigSource) -> Result<(), Box<dyn std::error::Error>> {
+    let editor = std::env::var("VISUAL")
+        .or(std::env::var("EDITOR"))
+        .unwrap_or("vi".to_string());
+
+    // Create the file if it doesn't exist yet
+    config_source.load_file()?;
+    let original = fs::read_to_string(&config_source.config_file)?;
+
+    // Edit a copy so that the app never sees an invalid config
+    let mut copy = tempfile::Builder::new().suffix(".toml").tempfile()?;
+    copy.write_all(original.as_bytes())?;
+
+    loop {
+        // The editor may come with arguments, like `code --wait`
+        let mut command = editor.split_whitespace();
+        let status = Command::new(command.next().ok_or("$EDITOR is empty")?)
+            .args(command)
+            .arg(copy.path())
+            .status()?;
+
+        if !status.success() {
+            return Err(format!("`{editor}` exited with {status}.").into());
+        }
+
+        let edited = fs::read_to_string(copy.path())?;
+
+        match Config::parse(&edited).and_then(|(config, _)| config.validate()) {
+            Ok(()) => {
+                if edited != original {
+                    config::write_atomically(&config_source.config_file, &edited)?;
+                }
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("Invalid config: {e}");
+                eprint!("Edit again? [Y/n] ");
+
+                let mut answer = String::new();
+                io::stdin().read_line(&mut answer)?;
+                if answer.trim().eq_ignore_ascii_case("n") {
+                    return Err("The config file has not been changed.".into());
+                }
+            }
+        }
+    }
+}
+
+// Strings are shown without quotes to be easier to use in scripts
+fn format_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Names of the profiles of the config file, along with whether they are active.
+pub fn list_profiles(
+    config_source: &ConfigSource,
+) -> Result<Vec<(String, bool)>, Box<dyn std::error::Error>> {
+    let config = config_source.load()?;
+
+    Ok(config
+        .profiles
+        .keys()
+        .map(|name| (name.clone(), config.active_profile.as_ref() == Some(name)))
+        .collect())
+}
+
+/// Makes `profile_name` the active profile, `None` to use the settings outside of any profile.
+pub fn use_profile(
+    config_source: &ConfigSource,
+    profile_name: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config_source.load()?;
+
+    // `save` checks that the profile exists
+    config_source.save(&Config {
+        active_profile: profile_name,
+        ..config
+    })?;
+
+    Ok(())
+}
+
+pub fn import_theme(
+    config_source: &ConfigSource,
+    path: &Path,
+    format: Option<ThemeFormat>,
+    name: Option<String>,
+    force: bool,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let theme = theme_import::import_file(path, format, name)?;
+
+    let file_name: String = theme
+        .name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+
+    let themes_dir = config_source.themes_dir();
+    fs::create_dir_all(&themes_dir)?;
+
+    let theme_path = themes_dir.join(format!("{file_name}.json"));
+    if theme_path.exists() && !force {
+        return Err(format!("{theme_path:?} already exists, use --force to overwrite it.").into());
+    }
+
+    fs::write(&theme_path, serde_json::to_string_pretty(&theme)?)?;
+
+    Ok(theme_path)
+}
+
+/// Sends `message` to the running app, see `gui::apply_message` for the messages.
+fn send_to_app(
+    config_source: &ConfigSource,
+    message: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let socket = config_source.control_socket();
+
+    match control::send(&socket, message) {
+        Ok(reply) => Ok(reply?),
+        Err(e) => Err(format!("unable to reach the app, is it running? ({e})").into()),
+    }
+}
+
+/// Adds `duration`, like `5m`, to the current session of the running app.
+pub fn extend_session(
+    config_source: &ConfigSource,
+    duration: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let time = duration::parse(duration)
+        .filter(|time| !time.is_zero())
+        .ok_or(format!(
+            "invalid duration `{duration}`, expected a duration like `5m` or `90s`"
+        ))?;
+
+    send_to_app(config_source, &format!("extend:{}", duration::format(time)))
+}
+
+/// Goes back to the state of the running app before the last skip or reset.
+pub fn undo(config_source: &ConfigSource) -> Result<(), Box<dyn std::error::Error>> {
+    send_to_app(config_source, "undo")
+}
+
+/// Stops the flow session of the running app, which goes on to the suggested break.
+pub fn stop(config_source: &ConfigSource) -> Result<(), Box<dyn std::error::Error>> {
+    send_to_app(config_source, "stop")
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum SchemaKind {
+    /// The `config.toml` file
+    Config,
+    /// The theme JSON files
+    Theme,
+}
+
+/// JSON Schema of the config file or of the theme files, for editor completion and validation.
+pub fn schema(kind: SchemaKind) -> Result<String, serde_json::Error> {
+    let schema = match kind {
+        SchemaKind::Config => schemars::schema_for!(Config),
+        SchemaKind::Theme => schemars::schema_for!(JsonTheme),
+    };
+
+    serde_json::to_string_pretty(&schema)
 }
 
 async fn run_pomodoro_checker(config: Config) {
@@ -30,9 +315,9 @@ async fn run_pomodoro_checker(config: Config) {
 
         if file_exists(&file_path).await {
             if let Some(remaining_time) =
-                get_remaining_time(&file_path, config.focus_duration as u64).await
+                get_remaining_time(&file_path, config.focus_duration.as_secs()).await
             {
-                let total_seconds = config.focus_duration as u64; // Total time for Pomodoro in seconds
+                let total_seconds = config.focus_duration.as_secs(); // Total time for Pomodoro in seconds
                 let remaining_seconds = remaining_time.as_secs();
                 let elapsed_seconds = total_seconds - remaining_seconds;
 
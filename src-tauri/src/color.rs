@@ -0,0 +1,202 @@
+use hex_color::HexColor;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rgb {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+/// Color space in which the gradients are computed.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Interpolation {
+    #[default]
+    Rgb,
+    Hsl,
+    Oklab,
+}
+
+impl Rgb {
+    pub fn parse(hex: &str) -> Option<Self> {
+        HexColor::parse(hex).ok().map(|color| Rgb {
+            red: color.r,
+            green: color.g,
+            blue: color.b,
+        })
+    }
+
+    pub fn to_hex(self) -> String {
+        format!("#{:02X}{:02X}{:02X}", self.red, self.green, self.blue)
+    }
+
+    fn to_floats(self) -> [f64; 3] {
+        [self.red, self.green, self.blue].map(|c| c as f64 / 255.0)
+    }
+
+    fn from_floats(rgb: [f64; 3]) -> Self {
+        let [red, green, blue] = rgb.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        Rgb { red, green, blue }
+    }
+}
+
+/// Color between `from` (`t = 0`) and `to` (`t = 1`).
+pub fn interpolate(from: Rgb, to: Rgb, t: f32, interpolation: Interpolation) -> Rgb {
+    let t = t.clamp(0.0, 1.0) as f64;
+
+    match interpolation {
+        Interpolation::Rgb => Rgb::from_floats(lerp3(from.to_floats(), to.to_floats(), t)),
+        Interpolation::Hsl => {
+            let [mut h1, s1, l1] = rgb_to_hsl(from.to_floats());
+            let [mut h2, s2, l2] = rgb_to_hsl(to.to_floats());
+
+            // The greys, white and black have no hue: keep the one of the other end instead of
+            // going through red
+            if s1 == 0.0 {
+                h1 = h2;
+            } else if s2 == 0.0 {
+                h2 = h1;
+            }
+
+            // Go around the hue circle by the shortest path
+            let mut hue_delta = h2 - h1;
+            if hue_delta > 180.0 {
+                hue_delta -= 360.0;
+            } else if hue_delta < -180.0 {
+                hue_delta += 360.0;
+            }
+
+            Rgb::from_floats(hsl_to_rgb([
+                (h1 + t * hue_delta).rem_euclid(360.0),
+                lerp(s1, s2, t),
+                lerp(l1, l2, t),
+            ]))
+        }
+        Interpolation::Oklab => Rgb::from_floats(oklab_to_rgb(lerp3(
+            rgb_to_oklab(from.to_floats()),
+            rgb_to_oklab(to.to_floats()),
+            t,
+        ))),
+    }
+}
+
+/// Color at `t` (between 0 and 1) of a gradient going through all the `stops`, evenly spaced.
+pub fn gradient(stops: &[Rgb], t: f32, interpolation: Interpolation) -> Option<Rgb> {
+    match stops {
+        [] => None,
+        [stop] => Some(*stop),
+        _ => {
+            let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+            let index = (position.floor() as usize).min(stops.len() - 2);
+
+            Some(interpolate(
+                stops[index],
+                stops[index + 1],
+                position - index as f32,
+                interpolation,
+            ))
+        }
+    }
+}
+
+/// Hex color between the `from` and `to` hex colors, `None` if one of them can't be parsed.
+pub fn mix(from: &str, to: &str, t: f32, interpolation: Interpolation) -> Option<String> {
+    Some(interpolate(Rgb::parse(from)?, Rgb::parse(to)?, t, interpolation).to_hex())
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    (1.0 - t) * a + t * b
+}
+
+fn lerp3(a: [f64; 3], b: [f64; 3], t: f64) -> [f64; 3] {
+    [
+        lerp(a[0], b[0], t),
+        lerp(a[1], b[1], t),
+        lerp(a[2], b[2], t),
+    ]
+}
+
+fn rgb_to_hsl([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+    let delta = max - min;
+
+    if delta == 0.0 {
+        return [0.0, 0.0, lightness];
+    }
+
+    let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+    let hue = if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+
+    [hue, saturation, lightness]
+}
+
+fn hsl_to_rgb([hue, saturation, lightness]: [f64; 3]) -> [f64; 3] {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = lightness - chroma / 2.0;
+
+    let (r, g, b) = match hue {
+        h if h < 60.0 => (chroma, x, 0.0),
+        h if h < 120.0 => (x, chroma, 0.0),
+        h if h < 180.0 => (0.0, chroma, x),
+        h if h < 240.0 => (0.0, x, chroma),
+        h if h < 300.0 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    [r + m, g + m, b + m]
+}
+
+fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// See https://bottosson.github.io/posts/oklab/
+fn rgb_to_oklab(rgb: [f64; 3]) -> [f64; 3] {
+    let [r, g, b] = rgb.map(srgb_to_linear);
+
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+fn oklab_to_rgb([lightness, a, b]: [f64; 3]) -> [f64; 3] {
+    let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+
+    [
+        4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+        -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+        -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+    ]
+    .map(linear_to_srgb)
+}
@@ -0,0 +1,165 @@
+use chrono::{Local, Timelike};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::time::Duration;
+use tokio::time::interval;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+// Local hours during which the light theme is used when the desktop has no preference
+const LIGHT_HOURS: Range<u32> = 7..19;
+
+pub fn from_time_of_day(hour: u32) -> ColorScheme {
+    if LIGHT_HOURS.contains(&hour) {
+        ColorScheme::Light
+    } else {
+        ColorScheme::Dark
+    }
+}
+
+pub async fn current() -> ColorScheme {
+    DesktopPreference::new().await.current().await
+}
+
+/// Calls `on_change` with the current color scheme, then every time it changes.
+pub async fn watch(mut on_change: impl FnMut(ColorScheme)) {
+    // Needed for the time of day fallback, and as a safety net if the portal signal is missed
+    let mut interval = interval(Duration::from_secs(60));
+
+    let desktop_preference = DesktopPreference::new().await;
+
+    #[cfg(target_os = "linux")]
+    let mut portal_changes = match &desktop_preference.connection {
+        Some(connection) => portal::setting_changes(connection).await.ok(),
+        None => None,
+    };
+
+    let mut current_color_scheme = None;
+
+    loop {
+        #[cfg(target_os = "linux")]
+        tokio::select! {
+            _ = interval.tick() => {},
+            _ = portal::next_change(&mut portal_changes) => {},
+        }
+        #[cfg(not(target_os = "linux"))]
+        interval.tick().await;
+
+        let color_scheme = desktop_preference.current().await;
+
+        if current_color_scheme != Some(color_scheme) {
+            current_color_scheme = Some(color_scheme);
+            on_change(color_scheme);
+        }
+    }
+}
+
+// The preference of the desktop, read through the same connection to the portal every time
+struct DesktopPreference {
+    #[cfg(target_os = "linux")]
+    connection: Option<zbus::Connection>,
+}
+
+impl DesktopPreference {
+    #[cfg(target_os = "linux")]
+    async fn new() -> Self {
+        let connection = match zbus::Connection::session().await {
+            Ok(connection) => Some(connection),
+            Err(e) => {
+                eprintln!("Unable to connect to the desktop portal: {e:?}.");
+                None
+            }
+        };
+
+        DesktopPreference { connection }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn new() -> Self {
+        DesktopPreference {}
+    }
+
+    async fn current(&self) -> ColorScheme {
+        match self.read().await {
+            Some(color_scheme) => color_scheme,
+            None => from_time_of_day(Local::now().hour()),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn read(&self) -> Option<ColorScheme> {
+        match portal::read_color_scheme(self.connection.as_ref()?).await {
+            Ok(1) => Some(ColorScheme::Dark),
+            Ok(2) => Some(ColorScheme::Light),
+            // 0 means "no preference"
+            Ok(_) => None,
+            Err(e) => {
+                eprintln!("Unable to read the color scheme from the desktop portal: {e:?}.");
+                None
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn read(&self) -> Option<ColorScheme> {
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod portal {
+    use futures::StreamExt;
+    use zbus::zvariant::{OwnedValue, Value};
+    use zbus::{Connection, MatchRule, MessageStream};
+
+    const DESTINATION: &str = "org.freedesktop.portal.Desktop";
+    const PATH: &str = "/org/freedesktop/portal/desktop";
+    const INTERFACE: &str = "org.freedesktop.portal.Settings";
+
+    pub async fn read_color_scheme(connection: &Connection) -> zbus::Result<u32> {
+        let reply = connection
+            .call_method(
+                Some(DESTINATION),
+                PATH,
+                Some(INTERFACE),
+                "Read",
+                &("org.freedesktop.appearance", "color-scheme"),
+            )
+            .await?;
+
+        let value: OwnedValue = reply.body().deserialize()?;
+        let mut value: Value = value.into();
+
+        // `Read` wraps the setting in one or two variants depending on the portal version
+        while let Value::Value(inner) = value {
+            value = *inner;
+        }
+
+        Ok(u32::try_from(value)?)
+    }
+
+    pub async fn setting_changes(connection: &Connection) -> zbus::Result<MessageStream> {
+        let rule = MatchRule::builder()
+            .msg_type(zbus::message::Type::Signal)
+            .interface(INTERFACE)?
+            .member("SettingChanged")?
+            .build();
+
+        MessageStream::for_match_rule(rule, connection, None).await
+    }
+
+    pub async fn next_change(changes: &mut Option<MessageStream>) {
+        match changes {
+            Some(stream) => {
+                if stream.next().await.is_none() {
+                    *changes = None;
+                }
+            }
+            None => futures::future::pending().await,
+        }
+    }
+}
@@ -1,47 +1,377 @@
+use crate::color::{Interpolation, Rgb};
+use crate::color_scheme::ColorScheme;
+use crate::do_not_disturb::DoNotDisturbBackend;
+use crate::duration;
+use crate::hooks::Hooks;
+use crate::idle::IdleTimePolicy;
+use crate::notification::{NotificationTemplates, Urgency};
 use crate::pomodoro;
-use serde::{Deserialize, Serialize};
+use crate::screen_lock::LockAction;
+use crate::theme::HEX_COLOR;
+use schemars::JsonSchema;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
-use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Version of the config file format written by this version of the app.
+pub const CONFIG_VERSION: i64 = 3;
+
+// Each migration upgrades a config file from the version at its index to the next one
+const MIGRATIONS: [fn(&mut toml::Table); CONFIG_VERSION as usize] =
+    [migrate_v0_to_v1, migrate_v1_to_v2, migrate_v2_to_v3];
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Invalid {
+        field: &'static str,
+        message: String,
+    },
+    Io(std::io::Error),
+    Parse(String),
+    Serialize(toml::ser::Error),
+    UnsupportedVersion(i64),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Invalid { field, message } => write!(f, "invalid `{field}`: {message}"),
+            ConfigError::Io(e) => write!(f, "{e}"),
+            ConfigError::Parse(message) => write!(f, "invalid config: {message}"),
+            ConfigError::Serialize(e) => write!(f, "unable to serialize the config: {e}"),
+            ConfigError::UnsupportedVersion(version) => write!(
+                f,
+                "config version {version} is not supported by this version of Pomodorolm (max version {CONFIG_VERSION})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+// Sent to the front-end when a Tauri command fails
+impl Serialize for ConfigError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (kind, field) = match self {
+            ConfigError::Invalid { field, .. } => ("invalid", Some(*field)),
+            ConfigError::Io(_) => ("io", None),
+            ConfigError::Parse(_) => ("parse", None),
+            ConfigError::Serialize(_) => ("serialize", None),
+            ConfigError::UnsupportedVersion(_) => ("unsupported_version", None),
+        };
+
+        let mut state = serializer.serialize_struct("ConfigError", 3)?;
+        state.serialize_field("kind", kind)?;
+        state.serialize_field("field", &field)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl From<toml::ser::Error> for ConfigError {
+    fn from(e: toml::ser::Error) -> Self {
+        ConfigError::Serialize(e)
+    }
+}
+
+/// A session of a custom `sequence`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct SequenceStep {
+    /// Sound file played at the start of the step, the one of its `kind` if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<String>,
+    /// Start the step without waiting for the user
+    #[serde(default)]
+    pub auto_start: bool,
+    /// Color of the timer during the step, the one of the theme for its `kind` if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(pattern(HEX_COLOR))]
+    pub color: Option<String>,
+    /// Duration of the step, like `25m`
+    #[serde(with = "duration::human")]
+    #[schemars(with = "duration::HumanDuration")]
+    pub duration: Duration,
+    /// Kind of session, for the colors, the sounds and the notifications
+    #[serde(default = "default_step_kind")]
+    pub kind: pomodoro::SessionType,
+    /// Name of the step, used as the label of its sessions
+    pub name: String,
+}
+
+fn default_step_kind() -> pomodoro::SessionType {
+    pomodoro::SessionType::Focus
+}
+
+/// Built-in sequences.
+#[derive(Copy, Debug, Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub enum SequencePreset {
+    /// Focus rounds and short breaks, with a long break every `max_round_number` rounds
+    #[default]
+    #[serde(rename = "classic")]
+    Classic,
+    /// 52 minutes of focus and 17 minutes of break
+    #[serde(rename = "52-17")]
+    FiftyTwoSeventeen,
+    /// 90 minutes of focus and 20 minutes of break
+    #[serde(rename = "ultradian")]
+    Ultradian,
+}
+
+/// The sessions the timer goes through in a loop: the name of a built-in preset or a list of
+/// steps.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum Sequence {
+    Preset(SequencePreset),
+    Steps(Vec<SequenceStep>),
+}
+
+impl Default for Sequence {
+    fn default() -> Self {
+        Sequence::Preset(SequencePreset::default())
+    }
+}
+
+/// Named set of settings overriding the ones of the config, like `[profiles.deep]`.
+/// Settings that are not set keep the value of the config.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct Profile {
+    /// Start the long breaks without waiting for the user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_start_long_break_timer: Option<bool>,
+    /// Start the short breaks without waiting for the user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_start_short_break_timer: Option<bool>,
+    /// Start the focus sessions without waiting for the user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_start_work_timer: Option<bool>,
+    /// Sound file played at the start of the focus sessions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focus_audio: Option<String>,
+    /// Duration of the focus sessions, like `25m`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "duration::human_option"
+    )]
+    #[schemars(with = "Option<duration::HumanDuration>")]
+    pub focus_duration: Option<Duration>,
+    /// Sound file played at the start of the long breaks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_break_audio: Option<String>,
+    /// Duration of the long breaks, like `25m`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "duration::human_option"
+    )]
+    #[schemars(with = "Option<duration::HumanDuration>")]
+    pub long_break_duration: Option<Duration>,
+    /// Number of focus sessions before a long break
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 1))]
+    pub max_round_number: Option<u16>,
+    /// Sound file played at the start of the short breaks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_break_audio: Option<String>,
+    /// Duration of the short breaks, like `25m`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "duration::human_option"
+    )]
+    #[schemars(with = "Option<duration::HumanDuration>")]
+    pub short_break_duration: Option<Duration>,
+}
+
+/// Settings of the `config.toml` file.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 pub struct Config {
+    /// Name of the profile of `profiles` to use
+    #[serde(default)]
+    pub active_profile: Option<String>,
+    /// Keep the window above the other ones
     pub always_on_top: bool,
+    /// Quit the app at the end of this type of session
     pub auto_quit: Option<pomodoro::SessionType>,
-    pub auto_start_break_timer: bool,
+    /// Time to wait before starting a session that starts by itself, like `10s`
+    #[serde(default, with = "duration::human")]
+    #[schemars(with = "duration::HumanDuration")]
+    pub auto_start_delay: Duration,
+    /// Start the long breaks without waiting for the user
+    pub auto_start_long_break_timer: bool,
+    /// Start the timer when the app starts
     #[serde(default)]
     pub auto_start_on_app_startup: bool,
+    /// Start the short breaks without waiting for the user
+    pub auto_start_short_break_timer: bool,
+    /// Start the focus sessions without waiting for the user
     pub auto_start_work_timer: bool,
+    /// Show a notification with the remaining time during the breaks, on Linux
+    #[serde(default)]
+    pub break_countdown_notification: bool,
+    /// Focus rounds to complete in a day, whatever the cycles they are in. Once reached, the
+    /// sessions don't start by themselves anymore. No target if not set
+    #[serde(default)]
+    #[schemars(range(min = 1))]
+    pub daily_focus_target: Option<u16>,
+    /// Sound file played when the daily focus target is reached, the long break one if not set
+    #[serde(default)]
+    pub daily_target_audio: Option<String>,
+    /// Label of the focus sessions
     #[serde(default = "default_focus_label")]
     pub default_focus_label: String,
+    /// Label of the long breaks
     #[serde(default = "default_long_break_label")]
     pub default_long_break_label: String,
+    /// Label of the short breaks
     #[serde(default = "default_short_break_label")]
     pub default_short_break_label: String,
+    /// Show a notification at the end of each session
     pub desktop_notifications: bool,
+    /// Silence the notifications of the other apps during the focus sessions, on Linux, with the
+    /// `gnome`, `freedesktop` (KDE), `mako` or `dunst` backend, or the one of the current desktop
+    /// with `auto`. Never if not set
+    #[serde(default)]
+    pub do_not_disturb: Option<DoNotDisturbBackend>,
+    /// Time the "+N min" action of the notifications adds to the session that just ended
+    #[serde(default = "default_extend_duration", with = "duration::human")]
+    #[schemars(with = "duration::HumanDuration")]
+    pub extend_duration: Duration,
+    /// Part of the time spent in a flow session that the break after it lasts, `0.2` for a 10
+    /// minutes break after 50 minutes of focus
+    #[serde(default = "default_flow_break_ratio")]
+    #[schemars(range(max = 1))]
+    pub flow_break_ratio: f32,
+    /// Focus sessions count up until they are stopped, or until `max_session_duration`, instead
+    /// of lasting `focus_duration`
+    #[serde(default)]
+    pub flow_mode: bool,
+    /// Sound file played at the start of the focus sessions, the default one if not set
     pub focus_audio: Option<String>,
-    #[serde(alias = "pomodoro_duration")]
-    pub focus_duration: u16,
+    /// Duration of the focus sessions, like `25m`
+    #[serde(with = "duration::human")]
+    #[schemars(with = "duration::HumanDuration")]
+    pub focus_duration: Duration,
+    /// Color space used to compute the color of the timer during focus sessions
+    #[serde(default)]
+    pub gradient_interpolation: Interpolation,
+    /// Commands run when the sessions start, end, are paused, resumed or skipped
+    #[serde(default)]
+    pub hooks: Hooks,
+    /// Origins of the web pages allowed to call the HTTP API from a browser, like
+    /// `http://localhost:8080`
+    #[serde(default)]
+    pub http_api_allowed_origins: Vec<String>,
+    /// Port of localhost on which the status and the control of the timer are served over HTTP,
+    /// with `http_api_token`. Not served if not set
+    #[serde(default)]
+    #[schemars(range(min = 1))]
+    pub http_api_port: Option<u16>,
+    /// Token the HTTP requests need, as an `Authorization: Bearer` header or a `token` query
+    /// parameter. Required by `http_api_port`
+    #[serde(default)]
+    pub http_api_token: Option<String>,
+    /// Pause the focus sessions once the computer has been idle for this long, like `5m`, on
+    /// Linux. Never if not set
+    #[serde(default, with = "duration::human_option")]
+    #[schemars(with = "Option<duration::HumanDuration>")]
+    pub idle_pause_after: Option<Duration>,
+    /// What to do with the idle time of a session paused by `idle_pause_after` when you come back:
+    /// `ask`, `keep` it as focus time or `discard` it
+    #[serde(default)]
+    pub idle_time_policy: IdleTimePolicy,
+    /// What to do with the running focus session when the screen gets locked or the computer goes
+    /// to sleep, on Linux: `pause` it until you're back, `end` it or do `nothing`
+    #[serde(default)]
+    pub lock_action: LockAction,
+    /// Sound file played at the start of the long breaks, the default one if not set
     pub long_break_audio: Option<String>,
-    pub long_break_duration: u16,
+    /// Duration of the long breaks, like `25m`
+    #[serde(with = "duration::human")]
+    #[schemars(with = "duration::HumanDuration")]
+    pub long_break_duration: Duration,
+    /// Number of focus sessions before a long break
+    #[schemars(range(min = 1))]
     pub max_round_number: u16,
-    #[serde(default = "default_max_session_duration")]
-    pub max_session_duration: u16,
+    /// Maximum duration of a session, like `1h30m`
+    #[serde(default = "default_max_session_duration", with = "duration::human")]
+    #[schemars(with = "duration::HumanDuration")]
+    pub max_session_duration: Duration,
+    /// Hide the window in the tray when minimizing it
     pub minimize_to_tray: bool,
+    /// Hide the window in the tray when closing it
     pub minimize_to_tray_on_close: bool,
+    /// Mute all the sounds
     #[serde(default)]
     pub muted: bool,
+    /// Title and body of the desktop notifications sent at the end of the sessions
+    #[serde(default)]
+    pub notification_templates: NotificationTemplates,
+    /// Time before the notifications expire, like `30s`, `0` to keep them until dismissed, the
+    /// notification daemon decides if not set
+    #[serde(default, with = "duration::human_option")]
+    #[schemars(with = "Option<duration::HumanDuration>")]
+    pub notification_timeout: Option<Duration>,
+    /// Urgency of the notifications, `critical` ones usually stay until dismissed
+    #[serde(default)]
+    pub notification_urgency: Urgency,
+    /// Named sets of settings overriding the ones above, see `active_profile`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+    /// A notification replaces the previous one instead of piling up, on Linux
+    #[serde(default = "default_replace_notifications")]
+    pub replace_notifications: bool,
+    /// Sessions to go through: `classic`, `52-17`, `ultradian`, or a list of steps
+    #[serde(default)]
+    pub sequence: Sequence,
+    /// Sound file played at the start of the short breaks, the default one if not set
     pub short_break_audio: Option<String>,
-    pub short_break_duration: u16,
+    /// Duration of the short breaks, like `25m`
+    #[serde(with = "duration::human")]
+    #[schemars(with = "duration::HumanDuration")]
+    pub short_break_duration: Duration,
+    /// Time the "Snooze" action of the notifications holds the next session for
+    #[serde(default = "default_snooze_duration", with = "duration::human")]
+    #[schemars(with = "duration::HumanDuration")]
+    pub snooze_duration: Duration,
+    /// Start the app hidden in the tray
     #[serde(default)]
     pub start_minimized: bool,
+    /// Start the app when the system starts
     #[serde(default)]
     pub system_startup_auto_start: bool,
+    /// Name of the theme, in lowercase
     #[serde(default = "default_theme")]
     pub theme: String,
+    /// Theme to use when the desktop prefers a dark color scheme, `theme` if only `theme_light`
+    /// is set
+    #[serde(default)]
+    pub theme_dark: Option<String>,
+    /// Theme to use when the desktop prefers a light color scheme, `theme` if only `theme_dark`
+    /// is set
+    #[serde(default)]
+    pub theme_light: Option<String>,
+    /// Play a tick sound every second during focus sessions
     pub tick_sounds_during_work: bool,
+    /// Play a tick sound every second during breaks
     pub tick_sounds_during_break: bool,
+    /// Version of the config file format, files without a version were written before versioning was introduced
+    #[serde(default)]
+    pub version: i64,
+    /// Volume of the sounds, from 0 to 100
+    #[schemars(range(max = 100))]
     pub volume: Option<u16>,
 }
 
@@ -58,8 +388,24 @@ fn default_theme() -> String {
     "pomotroid".to_string()
 }
 
-fn default_max_session_duration() -> u16 {
-    90 * 60
+fn default_max_session_duration() -> Duration {
+    Duration::from_secs(90 * 60)
+}
+
+fn default_flow_break_ratio() -> f32 {
+    0.2
+}
+
+fn default_extend_duration() -> Duration {
+    Duration::from_secs(5 * 60)
+}
+
+fn default_replace_notifications() -> bool {
+    true
+}
+
+fn default_snooze_duration() -> Duration {
+    Duration::from_secs(5 * 60)
 }
 
 impl Config {
@@ -67,67 +413,805 @@ impl Config {
         config_dir.join(config_file_name.unwrap_or("config.toml".to_string()))
     }
 
+    /// The steps of the `sequence`, none for the classic cycle which follows the durations and
+    /// `max_round_number`.
+    pub fn sequence_steps(&self) -> Vec<SequenceStep> {
+        let preset = |focus_duration, break_duration| {
+            vec![
+                SequenceStep {
+                    audio: None,
+                    auto_start: self.auto_start_work_timer,
+                    color: None,
+                    duration: focus_duration,
+                    kind: pomodoro::SessionType::Focus,
+                    name: self.default_focus_label.clone(),
+                },
+                SequenceStep {
+                    audio: None,
+                    auto_start: self.auto_start_short_break_timer,
+                    color: None,
+                    duration: break_duration,
+                    kind: pomodoro::SessionType::ShortBreak,
+                    name: self.default_short_break_label.clone(),
+                },
+            ]
+        };
+
+        match &self.sequence {
+            Sequence::Preset(SequencePreset::Classic) => vec![],
+            Sequence::Preset(SequencePreset::FiftyTwoSeventeen) => {
+                preset(Duration::from_secs(52 * 60), Duration::from_secs(17 * 60))
+            }
+            Sequence::Preset(SequencePreset::Ultradian) => {
+                preset(Duration::from_secs(90 * 60), Duration::from_secs(20 * 60))
+            }
+            Sequence::Steps(steps) => steps.clone(),
+        }
+    }
+
+    pub fn default_label(&self, session_type: pomodoro::SessionType) -> &str {
+        match session_type {
+            pomodoro::SessionType::Focus => &self.default_focus_label,
+            pomodoro::SessionType::ShortBreak => &self.default_short_break_label,
+            pomodoro::SessionType::LongBreak => &self.default_long_break_label,
+        }
+    }
+
+    /// The config as sent to the front-end, which works with numbers of seconds instead of
+    /// durations like `25m`.
+    pub fn to_front_end(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+
+        for (key, duration) in [
+            ("auto_start_delay", self.auto_start_delay),
+            ("extend_duration", self.extend_duration),
+            ("focus_duration", self.focus_duration),
+            ("long_break_duration", self.long_break_duration),
+            ("max_session_duration", self.max_session_duration),
+            ("short_break_duration", self.short_break_duration),
+            ("snooze_duration", self.snooze_duration),
+        ] {
+            value[key] = if duration.subsec_nanos() == 0 {
+                duration.as_secs().into()
+            } else {
+                duration.as_secs_f64().into()
+            };
+        }
+
+        value
+    }
+
+    /// The theme to switch to when the desktop color scheme changes, `None` if neither
+    /// `theme_light` nor `theme_dark` is set. `theme` stands for the one that isn't set.
+    pub fn theme_for_color_scheme(&self, color_scheme: ColorScheme) -> Option<&str> {
+        if self.theme_light.is_none() && self.theme_dark.is_none() {
+            return None;
+        }
+
+        match color_scheme {
+            ColorScheme::Light => self.theme_light.as_deref(),
+            ColorScheme::Dark => self.theme_dark.as_deref(),
+        }
+        .or(Some(&self.theme))
+    }
+
+    /// Reads and validates the config file of `config_dir`, see `get_or_create_file`.
     pub fn get_or_create_from_disk(
         config_dir: &Path,
         config_file_name: Option<String>,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let config_file_path = Self::get_config_file_path(config_dir, config_file_name);
-
+    ) -> Result<Self, ConfigError> {
         // Create the config dir and the themes one if they don’t exist
         let _ = fs::create_dir_all(config_dir.join("themes/"));
 
+        let config =
+            Self::get_or_create_file(&Self::get_config_file_path(config_dir, config_file_name))?;
+        // A file edited by hand can have values the settings would refuse
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Reads the config file at `config_file_path`, creating it with the default config if it
+    /// doesn't exist and migrating it if it was written by an older version.
+    pub fn get_or_create_file(config_file_path: &Path) -> Result<Self, ConfigError> {
+        let config_file_path = config_file_path.to_path_buf();
+
+        if let Some(parent) = config_file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
         let metadata = fs::metadata(&config_file_path);
 
         Ok(if metadata.is_err() {
-            let mut file = OpenOptions::new()
-                .read(true)
-                .write(true)
-                .create(true)
-                .truncate(true)
-                .open(config_file_path)?;
-
             let default_config = Config {
                 ..Default::default()
             };
 
-            file.write_all(toml::to_string(&default_config)?.as_bytes())?;
+            default_config.save(&config_file_path)?;
             default_config
         } else {
             // Open the file
-            let toml_str = fs::read_to_string(config_file_path)?;
-            toml::from_str(toml_str.as_str())?
+            let toml_str = fs::read_to_string(&config_file_path)?;
+            let (config, migrated_from) = Self::parse(&toml_str)?;
+
+            if let Some(version) = migrated_from {
+                // Keep the original file around in case something went wrong
+                let mut backup_path = config_file_path.clone().into_os_string();
+                backup_path.push(format!(".v{version}.bak"));
+                fs::copy(&config_file_path, backup_path)?;
+
+                config.save(&config_file_path)?;
+            }
+
+            config
+        })
+    }
+
+    /// Config with the settings of the active profile applied, the one the app should run with.
+    pub fn with_active_profile(&self) -> Config {
+        match self
+            .active_profile
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+        {
+            Some(profile) => self.with_profile(profile),
+            None => self.clone(),
+        }
+    }
+
+    fn with_profile(&self, profile: &Profile) -> Config {
+        let config = self.clone();
+
+        Config {
+            auto_start_long_break_timer: profile
+                .auto_start_long_break_timer
+                .unwrap_or(config.auto_start_long_break_timer),
+            auto_start_short_break_timer: profile
+                .auto_start_short_break_timer
+                .unwrap_or(config.auto_start_short_break_timer),
+            auto_start_work_timer: profile
+                .auto_start_work_timer
+                .unwrap_or(config.auto_start_work_timer),
+            focus_audio: profile.focus_audio.clone().or(config.focus_audio),
+            focus_duration: profile.focus_duration.unwrap_or(config.focus_duration),
+            long_break_audio: profile.long_break_audio.clone().or(config.long_break_audio),
+            long_break_duration: profile
+                .long_break_duration
+                .unwrap_or(config.long_break_duration),
+            max_round_number: profile.max_round_number.unwrap_or(config.max_round_number),
+            short_break_audio: profile
+                .short_break_audio
+                .clone()
+                .or(config.short_break_audio),
+            short_break_duration: profile
+                .short_break_duration
+                .unwrap_or(config.short_break_duration),
+            ..config
+        }
+    }
+
+    /// Applies the changes made to the config returned by `with_active_profile`.
+    /// The settings overridden by the active profile are saved in the profile, the other ones in
+    /// the config itself.
+    pub fn apply_changes(&self, changed: Config) -> Config {
+        let mut config = Config {
+            profiles: self.profiles.clone(),
+            ..changed.clone()
+        };
+
+        let Some((name, profile)) = self
+            .active_profile
+            .as_ref()
+            .and_then(|name| Some((name, self.profiles.get(name)?)))
+        else {
+            return config;
+        };
+
+        let mut profile = profile.clone();
+        if profile.auto_start_long_break_timer.is_some() {
+            profile.auto_start_long_break_timer = Some(changed.auto_start_long_break_timer);
+            config.auto_start_long_break_timer = self.auto_start_long_break_timer;
+        }
+        if profile.auto_start_short_break_timer.is_some() {
+            profile.auto_start_short_break_timer = Some(changed.auto_start_short_break_timer);
+            config.auto_start_short_break_timer = self.auto_start_short_break_timer;
+        }
+        if profile.auto_start_work_timer.is_some() {
+            profile.auto_start_work_timer = Some(changed.auto_start_work_timer);
+            config.auto_start_work_timer = self.auto_start_work_timer;
+        }
+        if profile.focus_audio.is_some() {
+            profile.focus_audio = changed.focus_audio.clone();
+            config.focus_audio = self.focus_audio.clone();
+        }
+        if profile.focus_duration.is_some() {
+            profile.focus_duration = Some(changed.focus_duration);
+            config.focus_duration = self.focus_duration;
+        }
+        if profile.long_break_audio.is_some() {
+            profile.long_break_audio = changed.long_break_audio.clone();
+            config.long_break_audio = self.long_break_audio.clone();
+        }
+        if profile.long_break_duration.is_some() {
+            profile.long_break_duration = Some(changed.long_break_duration);
+            config.long_break_duration = self.long_break_duration;
+        }
+        if profile.max_round_number.is_some() {
+            profile.max_round_number = Some(changed.max_round_number);
+            config.max_round_number = self.max_round_number;
+        }
+        if profile.short_break_audio.is_some() {
+            profile.short_break_audio = changed.short_break_audio.clone();
+            config.short_break_audio = self.short_break_audio.clone();
+        }
+        if profile.short_break_duration.is_some() {
+            profile.short_break_duration = Some(changed.short_break_duration);
+            config.short_break_duration = self.short_break_duration;
+        }
+        config.profiles.insert(name.clone(), profile);
+
+        config
+    }
+
+    /// Checks the values that can't be enforced by the types themselves.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(name) = &self.active_profile
+            && !self.profiles.contains_key(name)
+        {
+            return Err(ConfigError::Invalid {
+                field: "active_profile",
+                message: format!("there is no profile named `{name}`"),
+            });
+        }
+
+        self.validate_settings()?;
+
+        for (name, profile) in &self.profiles {
+            self.with_profile(profile)
+                .validate_settings()
+                .map_err(|e| ConfigError::Invalid {
+                    field: "profiles",
+                    message: format!("profile `{name}`: {e}"),
+                })?;
+        }
+
+        Ok(())
+    }
+
+    fn validate_settings(&self) -> Result<(), ConfigError> {
+        let invalid = |field, message: String| Err(ConfigError::Invalid { field, message });
+
+        if self.max_session_duration.is_zero() {
+            return invalid(
+                "max_session_duration",
+                "should be greater than 0".to_string(),
+            );
+        }
+
+        for (field, duration) in [
+            ("extend_duration", self.extend_duration),
+            ("focus_duration", self.focus_duration),
+            ("long_break_duration", self.long_break_duration),
+            ("short_break_duration", self.short_break_duration),
+            ("snooze_duration", self.snooze_duration),
+        ] {
+            if duration.is_zero() || duration > self.max_session_duration {
+                return invalid(
+                    field,
+                    format!(
+                        "should be greater than 0 and at most {} (`max_session_duration`), got {}",
+                        duration::format(self.max_session_duration),
+                        duration::format(duration)
+                    ),
+                );
+            }
+        }
+
+        if self.auto_start_delay > self.max_session_duration {
+            return invalid(
+                "auto_start_delay",
+                format!(
+                    "should be at most {} (`max_session_duration`), got {}",
+                    duration::format(self.max_session_duration),
+                    duration::format(self.auto_start_delay)
+                ),
+            );
+        }
+
+        if self.idle_pause_after.is_some_and(|idle| idle.is_zero()) {
+            return invalid("idle_pause_after", "should be greater than 0".to_string());
+        }
+
+        if self.hooks.timeout.is_zero() {
+            return invalid("hooks", "`timeout` should be greater than 0".to_string());
+        }
+
+        if self.http_api_port == Some(0) {
+            return invalid("http_api_port", "should be at least 1".to_string());
+        }
+
+        if self.http_api_port.is_some() && self.http_api_token.as_deref().unwrap_or("").is_empty() {
+            return invalid(
+                "http_api_token",
+                "should be set when `http_api_port` is".to_string(),
+            );
+        }
+
+        if self.max_round_number == 0 {
+            return invalid("max_round_number", "should be at least 1".to_string());
+        }
+
+        if self.daily_focus_target == Some(0) {
+            return invalid("daily_focus_target", "should be at least 1".to_string());
+        }
+
+        if self.sequence == Sequence::Steps(vec![]) {
+            return invalid("sequence", "should have at least one step".to_string());
+        }
+
+        for step in self.sequence_steps() {
+            if step.duration.is_zero() || step.duration > self.max_session_duration {
+                return invalid(
+                    "sequence",
+                    format!(
+                        "step `{}`: should last more than 0 and at most {} (`max_session_duration`), got {}",
+                        step.name,
+                        duration::format(self.max_session_duration),
+                        duration::format(step.duration)
+                    ),
+                );
+            }
+
+            if let Some(color) = &step.color
+                && Rgb::parse(color).is_none()
+            {
+                return invalid(
+                    "sequence",
+                    format!("step `{}`: invalid color `{color}`", step.name),
+                );
+            }
+        }
+
+        if !(self.flow_break_ratio > 0.0 && self.flow_break_ratio <= 1.0) {
+            return invalid(
+                "flow_break_ratio",
+                format!(
+                    "should be greater than 0 and at most 1, got {}",
+                    self.flow_break_ratio
+                ),
+            );
+        }
+
+        if let Some(volume) = self.volume
+            && volume > 100
+        {
+            return invalid(
+                "volume",
+                format!("should be between 0 and 100, got {volume}"),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Validates the config and writes it atomically to `config_file_path`.
+    pub fn save(&self, config_file_path: &Path) -> Result<(), ConfigError> {
+        self.validate()?;
+
+        write_atomically(config_file_path, &toml::to_string(self)?)
+    }
+
+    /// Parses a config file, migrating it to the current version if needed.
+    /// Returns the config and the version it was migrated from, if any.
+    pub fn parse(toml_str: &str) -> Result<(Self, Option<i64>), ConfigError> {
+        let mut table: toml::Table =
+            toml::from_str(toml_str).map_err(|e| ConfigError::Parse(e.to_string()))?;
+
+        let version = match table.get("version") {
+            None => 0,
+            Some(value) => value.as_integer().ok_or(ConfigError::Parse(
+                "`version` should be an integer".to_string(),
+            ))?,
+        };
+
+        if !(0..=CONFIG_VERSION).contains(&version) {
+            return Err(ConfigError::UnsupportedVersion(version));
+        }
+
+        for migration in &MIGRATIONS[version as usize..] {
+            migration(&mut table);
+        }
+        table.insert("version".to_string(), toml::Value::Integer(CONFIG_VERSION));
+
+        let config = table
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::Parse(e.to_string()))?;
+
+        Ok((config, (version < CONFIG_VERSION).then_some(version)))
+    }
+}
+
+/// Writes `content` to a temporary file first and then renames it, so that a crash or a full
+/// disk never leaves a truncated file behind.
+pub fn write_atomically(path: &Path, content: &str) -> Result<(), ConfigError> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    file.write_all(content.as_bytes())?;
+    file.as_file().sync_all()?;
+    file.persist(path).map_err(|e| e.error)?;
+
+    // Make sure the rename itself is on disk
+    #[cfg(unix)]
+    fs::File::open(dir)?.sync_all()?;
+
+    Ok(())
+}
+
+/// Where the config is read from and written to, and the settings overridden on top of it.
+/// The settings are resolved as: defaults < config file < environment < command line.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConfigSource {
+    /// Directory of the custom themes, and of the config file if not set explicitly
+    pub config_dir: PathBuf,
+    pub config_file: PathBuf,
+    /// Directory of the history, the config directory if it was set explicitly
+    pub data_dir: PathBuf,
+    pub overrides: Overrides,
+}
+
+impl ConfigSource {
+    /// Uses `config_file`, or `config.toml` in `config_dir`, falling back to the
+    /// `config_dir_name` directory of the user config directory.
+    pub fn new(
+        config_dir_name: &str,
+        config_dir: Option<PathBuf>,
+        config_file: Option<PathBuf>,
+        overrides: Overrides,
+    ) -> Result<Self, ConfigError> {
+        let (config_dir, data_dir) = match config_dir {
+            Some(config_dir) => (config_dir.clone(), config_dir),
+            None => (
+                dirs::config_dir()
+                    .ok_or(ConfigError::Io(std::io::Error::other(
+                        "unable to find the user config directory",
+                    )))?
+                    .join(config_dir_name),
+                dirs::data_dir()
+                    .ok_or(ConfigError::Io(std::io::Error::other(
+                        "unable to find the user data directory",
+                    )))?
+                    .join(config_dir_name),
+            ),
+        };
+
+        Ok(ConfigSource {
+            config_file: config_file
+                .unwrap_or_else(|| Config::get_config_file_path(&config_dir, None)),
+            config_dir,
+            data_dir,
+            overrides,
         })
     }
+
+    pub fn themes_dir(&self) -> PathBuf {
+        self.config_dir.join("themes")
+    }
+
+    pub fn history_file(&self) -> PathBuf {
+        self.data_dir.join("history.jsonl")
+    }
+
+    /// Socket the running app listens to for the commands of the command line.
+    pub fn control_socket(&self) -> PathBuf {
+        self.data_dir.join("control.sock")
+    }
+
+    /// The config of the file, without the overrides. Not validated, so that the command line
+    /// can still fix an invalid file.
+    pub fn load_file(&self) -> Result<Config, ConfigError> {
+        // Create the themes dir if it doesn’t exist
+        let _ = fs::create_dir_all(self.themes_dir());
+
+        Config::get_or_create_file(&self.config_file)
+    }
+
+    /// The config of the file with the overrides applied.
+    pub fn load(&self) -> Result<Config, ConfigError> {
+        let config = self.overrides.apply(&self.load_file()?)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Saves a config returned by `load`, the overridden settings keep the value they have in
+    /// the file.
+    pub fn save(&self, config: &Config) -> Result<(), ConfigError> {
+        let config = if self.overrides.is_empty() {
+            config.clone()
+        } else {
+            self.overrides.revert(config, &self.load_file()?)?
+        };
+
+        config.save(&self.config_file)
+    }
+}
+
+/// Settings set from the environment (`POMODOROLM_FOCUS_DURATION=1500`) or the command line
+/// (`--set focus_duration=1500`) instead of the config file.
+/// Durations can be written like `25m`, other values are parsed as TOML values and used as
+/// plain strings if they are not valid TOML.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Overrides(Vec<(String, toml::Value)>);
+
+const ENV_PREFIX: &str = "POMODOROLM_";
+
+impl Overrides {
+    /// Reads the `POMODOROLM_<SETTING>` environment variables.
+    pub fn from_env() -> Self {
+        Self::from_vars(std::env::vars())
+    }
+
+    pub fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        let settings = settings_names();
+
+        Overrides(
+            vars.into_iter()
+                .filter_map(|(name, value)| {
+                    let key = name.strip_prefix(ENV_PREFIX)?.to_lowercase();
+                    // Other variables, like `POMODOROLM_CONFIG`, are not settings
+                    if !settings.contains(&key) {
+                        return None;
+                    }
+
+                    match parse_setting(&key, &value) {
+                        Ok(value) => Some((key, value)),
+                        Err(e) => {
+                            eprintln!("Ignoring {name}: {e}.");
+                            None
+                        }
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Parses `key=value` assignments.
+    pub fn parse(assignments: &[String]) -> Result<Self, ConfigError> {
+        assignments
+            .iter()
+            .map(|assignment| {
+                let (key, value) =
+                    assignment
+                        .split_once('=')
+                        .ok_or(ConfigError::Parse(format!(
+                            "`{assignment}` should be like `key=value`"
+                        )))?;
+                let key = key.trim();
+
+                Ok((key.to_string(), parse_setting(key, value)?))
+            })
+            .collect::<Result<_, _>>()
+            .map(Overrides)
+    }
+
+    /// Overrides applied after `self`, taking precedence over it.
+    pub fn and(mut self, other: Overrides) -> Self {
+        self.0.extend(other.0);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn apply(&self, config: &Config) -> Result<Config, ConfigError> {
+        if self.is_empty() {
+            return Ok(config.clone());
+        }
+
+        let mut table = toml::Table::try_from(config)?;
+        for (key, value) in &self.0 {
+            table.insert(key.clone(), value.clone());
+        }
+
+        table
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::Parse(format!("invalid override: {e}")))
+    }
+
+    /// Gives back their value in `file_config` to the overridden settings of `config`.
+    pub fn revert(&self, config: &Config, file_config: &Config) -> Result<Config, ConfigError> {
+        let mut table = toml::Table::try_from(config)?;
+        let file_table = toml::Table::try_from(file_config)?;
+
+        for (key, _) in &self.0 {
+            match file_table.get(key) {
+                Some(value) => table.insert(key.clone(), value.clone()),
+                None => table.remove(key),
+            };
+        }
+
+        table
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::Parse(e.to_string()))
+    }
+}
+
+/// Settings stored as durations like `25m` or `1h30m`.
+pub const DURATION_SETTINGS: [&str; 8] = [
+    "auto_start_delay",
+    "extend_duration",
+    "focus_duration",
+    "long_break_duration",
+    "max_session_duration",
+    "notification_timeout",
+    "short_break_duration",
+    "snooze_duration",
+];
+
+/// Names of the settings that can be set from the command line or the environment.
+pub fn settings_names() -> Vec<String> {
+    match serde_json::to_value(Config::default()) {
+        Ok(serde_json::Value::Object(settings)) => settings
+            .keys()
+            .filter(|key| !["profiles", "version"].contains(&key.as_str()))
+            .cloned()
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Parses the value of a setting given as a string, on the command line for example.
+pub fn parse_setting(key: &str, value: &str) -> Result<toml::Value, ConfigError> {
+    if !settings_names().iter().any(|setting| setting == key) {
+        return Err(ConfigError::Parse(format!("unknown setting `{key}`")));
+    }
+
+    let value = value.trim();
+
+    if DURATION_SETTINGS.contains(&key) {
+        return duration::parse(value)
+            .map(|duration| toml::Value::String(duration::format(duration)))
+            .ok_or(ConfigError::Parse(format!(
+                "invalid duration `{value}` for `{key}`, expected something like `25m` or `1h30m`"
+            )));
+    }
+
+    Ok(parse_value(value))
+}
+
+fn parse_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {value}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or(toml::Value::String(value.to_string()))
+}
+
+fn migrate_v0_to_v1(config: &mut toml::Table) {
+    // `focus_duration` used to be called `pomodoro_duration`
+    if let Some(duration) = config.remove("pomodoro_duration") {
+        config.entry("focus_duration").or_insert(duration);
+    }
+
+    // Unversioned files may miss the fields that were added over time
+    if let Ok(defaults) = toml::Table::try_from(Config::default()) {
+        for (key, value) in defaults {
+            config.entry(key).or_insert(value);
+        }
+    }
+}
+
+fn migrate_v1_to_v2(config: &mut toml::Table) {
+    // `auto_start_break_timer` was split into one setting for each type of break
+    split_auto_start_break_timer(config);
+
+    if let Some(toml::Value::Table(profiles)) = config.get_mut("profiles") {
+        for (_, profile) in profiles.iter_mut() {
+            if let toml::Value::Table(profile) = profile {
+                split_auto_start_break_timer(profile);
+            }
+        }
+    }
+}
+
+fn migrate_v2_to_v3(config: &mut toml::Table) {
+    // Durations used to be numbers of seconds, they are still read as such but written like `25m`
+    format_durations(config, &DURATION_SETTINGS);
+
+    if let Some(toml::Value::Table(profiles)) = config.get_mut("profiles") {
+        for (_, profile) in profiles.iter_mut() {
+            if let toml::Value::Table(profile) = profile {
+                format_durations(profile, &DURATION_SETTINGS);
+            }
+        }
+    }
+
+    if let Some(toml::Value::Array(steps)) = config.get_mut("sequence") {
+        for step in steps {
+            if let toml::Value::Table(step) = step {
+                format_durations(step, &["duration"]);
+            }
+        }
+    }
+}
+
+fn format_durations(table: &mut toml::Table, keys: &[&str]) {
+    for key in keys {
+        if let Some(value) = table.get_mut(*key)
+            && let toml::Value::Integer(seconds) = value
+            && let Ok(seconds) = u64::try_from(*seconds)
+        {
+            *value = toml::Value::String(duration::format(Duration::from_secs(seconds)));
+        }
+    }
+}
+
+fn split_auto_start_break_timer(table: &mut toml::Table) {
+    if let Some(auto_start) = table.remove("auto_start_break_timer") {
+        table.insert(
+            "auto_start_long_break_timer".to_string(),
+            auto_start.clone(),
+        );
+        table.insert("auto_start_short_break_timer".to_string(), auto_start);
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            active_profile: None,
             always_on_top: true,
             auto_quit: None,
-            auto_start_break_timer: true,
+            auto_start_delay: Duration::ZERO,
+            auto_start_long_break_timer: true,
             auto_start_on_app_startup: false,
+            auto_start_short_break_timer: true,
             auto_start_work_timer: true,
+            break_countdown_notification: false,
+            daily_focus_target: None,
+            daily_target_audio: None,
             default_focus_label: default_focus_label(),
             default_long_break_label: default_long_break_label(),
             default_short_break_label: default_short_break_label(),
             desktop_notifications: true,
+            do_not_disturb: None,
+            extend_duration: default_extend_duration(),
+            flow_break_ratio: default_flow_break_ratio(),
+            flow_mode: false,
             focus_audio: None,
-            focus_duration: 25 * 60,
+            focus_duration: Duration::from_secs(25 * 60),
+            gradient_interpolation: Interpolation::default(),
+            hooks: Hooks::default(),
+            http_api_allowed_origins: vec![],
+            http_api_port: None,
+            http_api_token: None,
+            idle_pause_after: None,
+            idle_time_policy: IdleTimePolicy::default(),
+            lock_action: LockAction::default(),
             long_break_audio: None,
-            long_break_duration: 20 * 60,
+            long_break_duration: Duration::from_secs(20 * 60),
             max_round_number: 4u16,
             max_session_duration: default_max_session_duration(),
             minimize_to_tray: true,
             minimize_to_tray_on_close: true,
             muted: false,
+            notification_templates: NotificationTemplates::default(),
+            notification_timeout: None,
+            notification_urgency: Urgency::default(),
+            profiles: BTreeMap::new(),
+            replace_notifications: default_replace_notifications(),
+            sequence: Sequence::default(),
             short_break_audio: None,
-            short_break_duration: 5 * 60,
+            short_break_duration: Duration::from_secs(5 * 60),
+            snooze_duration: default_snooze_duration(),
             start_minimized: false,
             system_startup_auto_start: false,
             theme: default_theme(),
+            theme_dark: None,
+            theme_light: None,
             tick_sounds_during_work: true,
             tick_sounds_during_break: true,
+            version: CONFIG_VERSION,
             volume: Some(100),
         }
     }
@@ -0,0 +1,80 @@
+// Control of the running app from the command line, through a Unix socket of the data
+// directory. Each connection sends one message, a line like `extend:300`, and reads one line
+// back: `ok` or `error: <reason>`.
+use std::io;
+use std::path::Path;
+
+#[cfg(unix)]
+use std::future::Future;
+#[cfg(unix)]
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+#[cfg(unix)]
+use tokio::net::UnixListener;
+
+/// Answers the messages sent to the socket at `path` with `handler`, until the listener fails.
+#[cfg(unix)]
+pub async fn serve<F, Fut>(path: &Path, handler: F) -> io::Result<()>
+where
+    F: Fn(String) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Left behind by an app that didn't quit cleanly
+    let _ = std::fs::remove_file(path);
+
+    let listener = UnixListener::bind(path)?;
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let handler = handler.clone();
+
+        tokio::spawn(async move {
+            let (reader, mut writer) = stream.into_split();
+            let mut message = String::new();
+            if BufReader::new(reader)
+                .read_line(&mut message)
+                .await
+                .is_err()
+            {
+                return;
+            }
+
+            let reply = match handler(message.trim().to_string()).await {
+                Ok(()) => "ok\n".to_string(),
+                Err(e) => format!("error: {e}\n"),
+            };
+            let _ = writer.write_all(reply.as_bytes()).await;
+        });
+    }
+}
+
+/// Sends `message` to the app listening at `path`.
+#[cfg(unix)]
+pub fn send(path: &Path, message: &str) -> io::Result<Result<(), String>> {
+    use std::io::{BufRead, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(path)?;
+    writeln!(stream, "{message}")?;
+
+    let mut reply = String::new();
+    std::io::BufReader::new(stream).read_line(&mut reply)?;
+
+    Ok(match reply.trim() {
+        "ok" => Ok(()),
+        reply => Err(reply
+            .strip_prefix("error: ")
+            .unwrap_or("no answer from the app")
+            .to_string()),
+    })
+}
+
+#[cfg(not(unix))]
+pub fn send(_path: &Path, _message: &str) -> io::Result<Result<(), String>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "controlling the app from the command line is only supported on Unix systems",
+    ))
+}
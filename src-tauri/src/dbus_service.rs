@@ -0,0 +1,203 @@
+// The running timer as a service of the session bus, so that the desktop extensions and the
+// scripts using `busctl` can follow and control it:
+//
+//     busctl --user call org.jousse.vincent.Pomodorolm /org/jousse/vincent/Pomodorolm \
+//         org.jousse.vincent.Pomodorolm Play
+use crate::pomodoro::{Pomodoro, SessionStatus, SessionType};
+use futures::future::BoxFuture;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::watch;
+use zbus::object_server::SignalEmitter;
+use zbus::{connection, fdo, interface};
+
+pub const SERVICE_NAME: &str = "org.jousse.vincent.Pomodorolm";
+pub const OBJECT_PATH: &str = "/org/jousse/vincent/Pomodorolm";
+
+/// What the service tells about the timer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimerState {
+    pub session_type: SessionType,
+    pub status: SessionStatus,
+    /// Whole seconds left in the session
+    pub remaining: u64,
+    pub round: u16,
+    pub label: String,
+}
+
+impl TimerState {
+    /// The state of `pomodoro`, with `default_label` if the session has no label of its own.
+    pub fn of(pomodoro: &Pomodoro, default_label: &str) -> Self {
+        let session = &pomodoro.current_session;
+
+        TimerState {
+            session_type: session.session_type,
+            status: session.status,
+            remaining: pomodoro
+                .duration_of_session(session)
+                .saturating_sub(session.current_time)
+                .as_secs(),
+            round: pomodoro.current_work_round_number,
+            label: session
+                .label
+                .clone()
+                .unwrap_or_else(|| default_label.to_string()),
+        }
+    }
+}
+
+type Handler = Arc<dyn Fn(String) -> BoxFuture<'static, Result<(), String>> + Send + Sync>;
+
+struct Timer {
+    handler: Handler,
+    state: TimerState,
+}
+
+impl Timer {
+    async fn send(&self, message: &str) -> fdo::Result<()> {
+        (self.handler)(message.to_string())
+            .await
+            .map_err(fdo::Error::Failed)
+    }
+}
+
+#[interface(name = "org.jousse.vincent.Pomodorolm")]
+impl Timer {
+    async fn play(&self) -> fdo::Result<()> {
+        self.send("play").await
+    }
+
+    async fn pause(&self) -> fdo::Result<()> {
+        self.send("pause").await
+    }
+
+    async fn skip(&self) -> fdo::Result<()> {
+        self.send("skip").await
+    }
+
+    async fn reset_round(&self) -> fdo::Result<()> {
+        self.send("reset_round").await
+    }
+
+    async fn reset_session(&self) -> fdo::Result<()> {
+        self.send("reset_session").await
+    }
+
+    async fn quit(&self) -> fdo::Result<()> {
+        self.send("quit").await
+    }
+
+    /// `Focus`, `ShortBreak` or `LongBreak`
+    #[zbus(property)]
+    fn session_type(&self) -> String {
+        format!("{:?}", self.state.session_type)
+    }
+
+    /// `NotStarted`, `Running` or `Paused`
+    #[zbus(property)]
+    fn status(&self) -> String {
+        format!("{:?}", self.state.status)
+    }
+
+    /// Seconds left in the session
+    #[zbus(property)]
+    fn remaining(&self) -> u64 {
+        self.state.remaining
+    }
+
+    #[zbus(property)]
+    fn round(&self) -> u16 {
+        self.state.round
+    }
+
+    #[zbus(property)]
+    fn label(&self) -> String {
+        self.state.label.clone()
+    }
+
+    /// Sent with all the properties whenever one of them changes
+    #[zbus(signal)]
+    async fn state_changed(
+        emitter: &SignalEmitter<'_>,
+        session_type: &str,
+        status: &str,
+        remaining: u64,
+        round: u16,
+        label: &str,
+    ) -> zbus::Result<()>;
+}
+
+/// Serves the timer on the session bus as `SERVICE_NAME`, see `serve_on`.
+pub async fn serve<F, Fut>(handler: F, states: watch::Receiver<TimerState>) -> zbus::Result<()>
+where
+    F: Fn(String) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+    serve_on(
+        connection::Builder::session()?.name(SERVICE_NAME)?,
+        handler,
+        states,
+    )
+    .await
+}
+
+/// Serves the timer on the connection of `builder`, sending the messages of the methods, like
+/// `play`, to `handler` and telling the clients about the changes of `states`, until `states`
+/// is closed.
+pub async fn serve_on<F, Fut>(
+    builder: connection::Builder<'_>,
+    handler: F,
+    mut states: watch::Receiver<TimerState>,
+) -> zbus::Result<()>
+where
+    F: Fn(String) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+    let timer = Timer {
+        handler: Arc::new(move |message| Box::pin(handler(message))),
+        state: states.borrow_and_update().clone(),
+    };
+    let connection = builder.serve_at(OBJECT_PATH, timer)?.build().await?;
+    let interface = connection
+        .object_server()
+        .interface::<_, Timer>(OBJECT_PATH)
+        .await?;
+
+    while states.changed().await.is_ok() {
+        let state = states.borrow_and_update().clone();
+        let previous = std::mem::replace(&mut interface.get_mut().await.state, state.clone());
+        if previous == state {
+            continue;
+        }
+
+        let emitter = interface.signal_emitter();
+        let timer = interface.get().await;
+        if previous.session_type != state.session_type {
+            timer.session_type_changed(emitter).await?;
+        }
+        if previous.status != state.status {
+            timer.status_changed(emitter).await?;
+        }
+        if previous.remaining != state.remaining {
+            timer.remaining_changed(emitter).await?;
+        }
+        if previous.round != state.round {
+            timer.round_changed(emitter).await?;
+        }
+        if previous.label != state.label {
+            timer.label_changed(emitter).await?;
+        }
+
+        Timer::state_changed(
+            emitter,
+            &timer.session_type(),
+            &timer.status(),
+            state.remaining,
+            state.round,
+            &state.label,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,308 @@
+// Silences the notifications of the other apps during the focus sessions
+use crate::pomodoro::{Pomodoro, SessionStatus, SessionType};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use tokio::process::Command;
+
+/// How to silence the notifications of the desktop.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DoNotDisturbBackend {
+    /// The backend of the current desktop
+    Auto,
+    /// The `show-banners` setting of GNOME
+    Gnome,
+    /// The notification inhibition of the freedesktop notification servers, like the one of KDE
+    Freedesktop,
+    /// The `do-not-disturb` mode of mako, to be defined in its config
+    Mako,
+    /// The pause of dunst
+    Dunst,
+}
+
+/// Something able to silence the notifications of the desktop.
+pub trait DoNotDisturb {
+    fn set(&mut self, silenced: bool) -> impl Future<Output = Result<(), String>> + Send;
+
+    /// Whether the notifications are already silenced, by the user for example.
+    fn is_silenced(&mut self) -> impl Future<Output = Result<bool, String>> + Send;
+}
+
+/// The notifications should be silenced during the running focus sessions only.
+pub fn should_silence(pomodoro: &Pomodoro) -> bool {
+    let session = &pomodoro.current_session;
+
+    session.session_type == SessionType::Focus
+        && session.status == SessionStatus::Running
+        && session.start_delay.is_zero()
+}
+
+/// Silences the notifications with `backend` when a focus session starts, and brings them back
+/// when it ends, unless they were already silenced before.
+pub struct DoNotDisturbSwitch<B> {
+    backend: B,
+    silenced: bool,
+    // Left as they are when the focus session ends
+    already_silenced: bool,
+}
+
+impl<B: DoNotDisturb> DoNotDisturbSwitch<B> {
+    pub fn new(backend: B) -> Self {
+        DoNotDisturbSwitch {
+            backend,
+            silenced: false,
+            already_silenced: false,
+        }
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Silences the notifications or brings them back if the session of `pomodoro` changed.
+    pub async fn update(&mut self, pomodoro: &Pomodoro) -> Result<(), String> {
+        self.switch(should_silence(pomodoro)).await
+    }
+
+    /// Brings the notifications back, before quitting for example.
+    pub async fn restore(&mut self) -> Result<(), String> {
+        self.switch(false).await
+    }
+
+    async fn switch(&mut self, silenced: bool) -> Result<(), String> {
+        if silenced == self.silenced {
+            return Ok(());
+        }
+
+        // A failing backend is only tried again on the next change, not on every tick
+        self.silenced = silenced;
+
+        if silenced {
+            self.already_silenced = match self.backend.is_silenced().await {
+                Ok(already_silenced) => already_silenced,
+                Err(e) => {
+                    // Nothing is changed if it can't be restored
+                    self.already_silenced = true;
+                    return Err(e);
+                }
+            };
+        }
+        if self.already_silenced {
+            return Ok(());
+        }
+
+        self.backend.set(silenced).await
+    }
+}
+
+/// The backend `auto` stands for on `current_desktop`, a value of `XDG_CURRENT_DESKTOP`, with
+/// the commands for which `is_installed` is true.
+pub fn detect(
+    current_desktop: &str,
+    is_installed: impl Fn(&str) -> bool,
+) -> Option<DoNotDisturbBackend> {
+    let is_desktop = |name: &str| {
+        current_desktop
+            .split(':')
+            .any(|desktop| desktop.eq_ignore_ascii_case(name))
+    };
+
+    if is_desktop("GNOME") {
+        Some(DoNotDisturbBackend::Gnome)
+    } else if is_desktop("KDE") {
+        Some(DoNotDisturbBackend::Freedesktop)
+    } else if is_installed("makoctl") {
+        Some(DoNotDisturbBackend::Mako)
+    } else if is_installed("dunstctl") {
+        Some(DoNotDisturbBackend::Dunst)
+    } else {
+        None
+    }
+}
+
+fn is_on_path(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|directory| directory.join(program).is_file())
+    })
+}
+
+/// Silences the notifications of the current desktop with one of the backends, on Linux.
+pub struct DesktopDoNotDisturb {
+    backend: DoNotDisturbBackend,
+    #[cfg(target_os = "linux")]
+    inhibition: Option<freedesktop::Inhibition>,
+}
+
+impl DesktopDoNotDisturb {
+    /// `None` if `auto` doesn't find any backend for the current desktop.
+    pub fn new(backend: DoNotDisturbBackend) -> Option<Self> {
+        let backend = match backend {
+            DoNotDisturbBackend::Auto => detect(
+                &std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default(),
+                is_on_path,
+            )?,
+            backend => backend,
+        };
+
+        Some(DesktopDoNotDisturb {
+            backend,
+            #[cfg(target_os = "linux")]
+            inhibition: None,
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn inhibit(&mut self, silenced: bool) -> Result<(), String> {
+        match (silenced, self.inhibition.take()) {
+            (true, None) => {
+                self.inhibition = Some(freedesktop::inhibit().await.map_err(|e| e.to_string())?);
+            }
+            (false, Some(inhibition)) => {
+                freedesktop::uninhibit(inhibition)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+            (_, inhibition) => self.inhibition = inhibition,
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn inhibit(&mut self, _silenced: bool) -> Result<(), String> {
+        Err("the freedesktop notifications are only available on Linux".to_string())
+    }
+}
+
+impl DoNotDisturb for DesktopDoNotDisturb {
+    async fn set(&mut self, silenced: bool) -> Result<(), String> {
+        match self.backend {
+            DoNotDisturbBackend::Gnome => {
+                let show_banners = if silenced { "false" } else { "true" };
+                run(
+                    "gsettings",
+                    &[
+                        "set",
+                        "org.gnome.desktop.notifications",
+                        "show-banners",
+                        show_banners,
+                    ],
+                )
+                .await
+            }
+            DoNotDisturbBackend::Freedesktop => self.inhibit(silenced).await,
+            DoNotDisturbBackend::Mako => {
+                let flag = if silenced { "-a" } else { "-r" };
+                run("makoctl", &["mode", flag, "do-not-disturb"]).await
+            }
+            DoNotDisturbBackend::Dunst => {
+                let paused = if silenced { "true" } else { "false" };
+                run("dunstctl", &["set-paused", paused]).await
+            }
+            // Replaced by the detected backend in `new`
+            DoNotDisturbBackend::Auto => Ok(()),
+        }
+    }
+
+    async fn is_silenced(&mut self) -> Result<bool, String> {
+        match self.backend {
+            DoNotDisturbBackend::Gnome => {
+                let show_banners = output(
+                    "gsettings",
+                    &["get", "org.gnome.desktop.notifications", "show-banners"],
+                )
+                .await?;
+                Ok(show_banners.trim() == "false")
+            }
+            // The inhibitions of the other apps are kept by the server, whatever this one does
+            DoNotDisturbBackend::Freedesktop => Ok(false),
+            DoNotDisturbBackend::Mako => {
+                let modes = output("makoctl", &["mode"]).await?;
+                Ok(modes.lines().any(|mode| mode.trim() == "do-not-disturb"))
+            }
+            DoNotDisturbBackend::Dunst => {
+                let paused = output("dunstctl", &["is-paused"]).await?;
+                Ok(paused.trim() == "true")
+            }
+            DoNotDisturbBackend::Auto => Ok(false),
+        }
+    }
+}
+
+// What `program` prints
+async fn output(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| format!("unable to run `{program}`: {e}"))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(format!("`{program}` failed with {}", output.status))
+    }
+}
+
+async fn run(program: &str, args: &[&str]) -> Result<(), String> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .await
+        .map_err(|e| format!("unable to run `{program}`: {e}"))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("`{program}` failed with {status}"))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod freedesktop {
+    use std::collections::HashMap;
+    use zbus::Connection;
+    use zbus::zvariant::Value;
+
+    const DESTINATION: &str = "org.freedesktop.Notifications";
+    const PATH: &str = "/org/freedesktop/Notifications";
+
+    // The notifications come back if the connection is closed, so it is kept with the cookie
+    pub struct Inhibition {
+        connection: Connection,
+        cookie: u32,
+    }
+
+    pub async fn inhibit() -> zbus::Result<Inhibition> {
+        let connection = Connection::session().await?;
+        let hints: HashMap<&str, Value> = HashMap::new();
+        let reply = connection
+            .call_method(
+                Some(DESTINATION),
+                PATH,
+                Some(DESTINATION),
+                "Inhibit",
+                &("org.jousse.vincent.Pomodorolm", "Focus session", hints),
+            )
+            .await?;
+
+        let cookie: u32 = reply.body().deserialize()?;
+        Ok(Inhibition { connection, cookie })
+    }
+
+    pub async fn uninhibit(inhibition: Inhibition) -> zbus::Result<()> {
+        inhibition
+            .connection
+            .call_method(
+                Some(DESTINATION),
+                PATH,
+                Some(DESTINATION),
+                "UnInhibit",
+                &(inhibition.cookie,),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
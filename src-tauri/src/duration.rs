@@ -0,0 +1,191 @@
+// Human-friendly durations, like `25m` or `1h30m`, for the settings and the command line
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::{Deserialize, Deserializer, Serializer};
+use std::borrow::Cow;
+use std::time::Duration;
+
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+/// Parses durations like `25m`, `1h30m`, `90s`, `1.5h`, `500ms` or `1h 30m`.
+/// Plain numbers are seconds, like in the config files written by older versions.
+pub fn parse(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    if let Some(seconds) = parse_amount(value, NANOS_PER_SECOND) {
+        return to_duration(seconds);
+    }
+
+    let mut nanos: u128 = 0;
+    let mut chars = value.chars().filter(|c| !c.is_whitespace()).peekable();
+
+    while chars.peek().is_some() {
+        let mut number = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+            number.push(c);
+        }
+
+        let mut unit = String::new();
+        while let Some(c) = chars.next_if(char::is_ascii_alphabetic) {
+            unit.push(c);
+        }
+
+        // A number without unit after another one, like `25m30`, is ambiguous
+        let unit_nanos = match unit.as_str() {
+            "h" => 60 * 60 * NANOS_PER_SECOND,
+            "m" => 60 * NANOS_PER_SECOND,
+            "s" => NANOS_PER_SECOND,
+            "ms" => NANOS_PER_SECOND / 1000,
+            _ => return None,
+        };
+
+        nanos = nanos.checked_add(parse_amount(&number, unit_nanos)?)?;
+    }
+
+    to_duration(nanos)
+}
+
+// Parses a number like `25` or `1.5` of a unit lasting `unit_nanos`, in nanoseconds
+fn parse_amount(number: &str, unit_nanos: u128) -> Option<u128> {
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let digits = |part: &str| part.len() <= 18 && part.chars().all(|c| c.is_ascii_digit());
+    if whole.is_empty() || !digits(whole) || !digits(fraction) {
+        return None;
+    }
+
+    let whole: u128 = whole.parse().ok()?;
+    let fraction = match fraction {
+        "" => 0,
+        _ => fraction.parse::<u128>().ok()? * unit_nanos / 10u128.pow(fraction.len() as u32),
+    };
+
+    whole.checked_mul(unit_nanos)?.checked_add(fraction)
+}
+
+fn to_duration(nanos: u128) -> Option<Duration> {
+    let seconds = u64::try_from(nanos / NANOS_PER_SECOND).ok()?;
+    Some(Duration::new(seconds, (nanos % NANOS_PER_SECOND) as u32))
+}
+
+/// Formats `duration` like `1h30m` or `1s500ms`, to the millisecond, the opposite of `parse`.
+pub fn format(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+    let milliseconds = u64::from(duration.subsec_millis());
+
+    let formatted: String = [
+        (hours, "h"),
+        (minutes, "m"),
+        (seconds, "s"),
+        (milliseconds, "ms"),
+    ]
+    .iter()
+    .filter(|(amount, _)| *amount > 0)
+    .map(|(amount, unit)| format!("{amount}{unit}"))
+    .collect();
+
+    if formatted.is_empty() {
+        "0s".to_string()
+    } else {
+        formatted
+    }
+}
+
+// What a duration can be written as in the config file
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Raw {
+    Seconds(u64),
+    FractionalSeconds(f64),
+    Text(String),
+}
+
+fn from_raw<E: serde::de::Error>(raw: Raw) -> Result<Duration, E> {
+    match raw {
+        Raw::Seconds(seconds) => Ok(Duration::from_secs(seconds)),
+        Raw::FractionalSeconds(seconds) => Duration::try_from_secs_f64(seconds)
+            .map_err(|_| E::custom(format!("invalid duration `{seconds}`"))),
+        Raw::Text(text) => parse(&text).ok_or(E::custom(format!(
+            "invalid duration `{text}`, expected something like `25m`, `1h30m` or `1500`"
+        ))),
+    }
+}
+
+/// Writes durations like `25m` and reads them back, as well as numbers of seconds.
+/// To be used with `#[serde(with = "duration::human")]`.
+pub mod human {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format(*duration))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        from_raw(Raw::deserialize(deserializer)?)
+    }
+}
+
+/// Same as `human`, for the optional durations.
+pub mod human_option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&format(*duration)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<Raw>::deserialize(deserializer)?
+            .map(from_raw)
+            .transpose()
+    }
+}
+
+/// Writes durations as numbers of seconds, with a fractional part only when they have one, and
+/// reads them back like `human`. To be used with `#[serde(with = "duration::seconds")]`.
+pub mod seconds {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        if duration.subsec_nanos() == 0 {
+            serializer.serialize_u64(duration.as_secs())
+        } else {
+            serializer.serialize_f64(duration.as_secs_f64())
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        from_raw(Raw::deserialize(deserializer)?)
+    }
+}
+
+/// Schema of the durations read by `human`, for `#[schemars(with = "duration::HumanDuration")]`.
+pub struct HumanDuration;
+
+impl JsonSchema for HumanDuration {
+    fn schema_name() -> Cow<'static, str> {
+        "Duration".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "A duration like `25m`, `1h30m`, `1.5h` or `500ms`, or a number of seconds",
+            "anyOf": [
+                { "type": "number", "minimum": 0 },
+                {
+                    "type": "string",
+                    "pattern": r"^\s*(\d+(\.\d+)?\s*(h|m|s|ms)?\s*)+$"
+                }
+            ]
+        })
+    }
+}
@@ -3,201 +3,213 @@
 // Fix for https://github.com/tauri-apps/tauri/issues/12382
 #![allow(deprecated)]
 
-use crate::config::Config;
+use crate::color::Rgb;
+use crate::color_scheme;
+use crate::config::{Config, ConfigError, ConfigSource, SequenceStep};
+#[cfg(unix)]
+use crate::control;
+#[cfg(target_os = "linux")]
+use crate::dbus_service::{self, TimerState};
+use crate::do_not_disturb::{DesktopDoNotDisturb, DoNotDisturbSwitch};
+use crate::duration;
+use crate::history::{Event, History};
+use crate::hooks;
+use crate::http_api;
 use crate::icon;
+use crate::idle::{self, IdleEvent, IdleTimePolicy, IdleWatcher};
+use crate::notification::{self, DesktopNotification, NotificationContext, NotificationEvent};
+#[cfg(target_os = "linux")]
+use crate::notification::{NotificationAction, Urgency, freedesktop};
 use crate::pomodoro;
+use crate::screen_lock::{DesktopEventSource, LockWatcher, Reaction, SystemEventSource};
 use crate::sound;
-use pomodoro::{Pomodoro, SessionStatus, SessionType};
+use crate::theme::{JsonTheme, Theme};
+use chrono::{Local, NaiveDate};
+use pomodoro::{Pomodoro, SessionStatus, SessionType, UndoStack};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::fs::OpenOptions;
-use std::io::Write;
 use std::sync::Arc;
 use std::time::Duration;
 use tauri::AppHandle;
 use tauri::Runtime;
-use tauri::menu::{MenuBuilder, MenuItemBuilder};
+use tauri::menu::{CheckMenuItemBuilder, MenuBuilder, MenuItemBuilder, SubmenuBuilder};
 use tauri::tray::{MouseButton, TrayIconBuilder, TrayIconEvent};
 use tauri::{Manager, path::BaseDirectory};
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, watch};
 use tokio::time; // 1.3.0 //
 pub struct AppState(Arc<Mutex<App>>);
+// Only managed if `do_not_disturb` is set
+struct DoNotDisturbState(Mutex<DoNotDisturbSwitch<DesktopDoNotDisturb>>);
 pub struct AppMenuStates<R: Runtime>(std::sync::Mutex<MenuStates<R>>);
 use futures::StreamExt;
-use hex_color::HexColor;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::Emitter;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
 use tauri_plugin_notification::{NotificationExt, PermissionState};
 use tokio_stream::wrappers::IntervalStream;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug)]
 struct App {
+    // The theme shown, `config.theme` or the one of the desktop color scheme, see `theme_light`
+    // and `theme_dark`
+    active_theme: String,
     config: Config,
-    config_dir_name: String,
+    config_source: ConfigSource,
+    // The pomodoro right before the last session was completed, until something else happens
+    ended: Option<Pomodoro>,
+    // Sessions that ended since the app started, completed or skipped
+    ended_sessions: u64,
     pomodoro: pomodoro::Pomodoro,
+    states: StateSenders,
+    themes: Vec<Theme>,
+    // The day `pomodoro.completed_focus_rounds` counts the rounds of
+    today: NaiveDate,
+    undo_stack: UndoStack,
 }
 
-struct MenuStates<R: Runtime> {
-    toggle_visibility_menu: tauri::menu::MenuItem<R>,
-    toggle_play_menu: tauri::menu::MenuItem<R>,
+// The state of the timer followed by the D-Bus service and the HTTP API, see `publish_state`
+#[derive(Debug)]
+struct StateSenders {
+    // The payload of the `external-message` event
+    json: watch::Sender<String>,
+    #[cfg(target_os = "linux")]
+    timer: watch::Sender<TimerState>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-struct PlaySoundMessage {
-    sound_id: String,
-    quit_after_play: bool,
-}
+impl App {
+    fn current_theme(&self) -> Option<&Theme> {
+        let theme_named = |name: &str| {
+            self.themes
+                .iter()
+                .find(|theme| theme.name.to_lowercase() == name.to_lowercase())
+        };
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-struct Colors {
-    accent: String,
-    background: String,
-    background_light: String,
-    background_lightest: String,
-    focus_round: String,
-    focus_round_middle: String,
-    focus_round_end: String,
-    foreground: String,
-    foreground_darker: String,
-    foreground_darkest: String,
-    long_round: String,
-    short_round: String,
-}
+        // Same fallback as the front-end if the active theme doesn't exist
+        theme_named(&self.active_theme).or_else(|| theme_named("pomodorolm"))
+    }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-struct Theme {
-    colors: Colors,
-    name: String,
-}
+    // Fraction of the current session that has elapsed
+    fn progress(&self) -> f32 {
+        let duration = self
+            .pomodoro
+            .duration_of_session(&self.pomodoro.current_session);
 
-impl From<JsonTheme> for Theme {
-    fn from(json_theme: JsonTheme) -> Self {
-        let (focus_round_middle, focus_round_end) = match (
-            json_theme.colors.focus_round_middle,
-            json_theme.colors.focus_round_end,
-        ) {
-            (Some(middle), Some(end)) => (middle, end),
-            _ => match (
-                HexColor::parse(json_theme.colors.short_round.as_str()),
-                HexColor::parse(json_theme.colors.focus_round.as_str()),
-            ) {
-                // If middle or end are not provided, try to compute the middle color ourself
-                // It will be the middle gradient between the focus round color and the short round
-                // color
-                (
-                    Ok(HexColor {
-                        r: r1,
-                        g: g1,
-                        b: b1,
-                        a: _a1,
-                    }),
-                    Ok(HexColor {
-                        r: r2,
-                        g: g2,
-                        b: b2,
-                        a: _a2,
-                    }),
-                ) => {
-                    // Middle of the 2 colors
-                    let t = 0.5;
-                    // Compute the middle gradient color
-                    let r = ((1.0 - t) * r1 as f32 + t * r2 as f32).round() as u8;
-                    let g = ((1.0 - t) * g1 as f32 + t * g2 as f32).round() as u8;
-                    let b = ((1.0 - t) * b1 as f32 + t * b2 as f32).round() as u8;
-                    // RGB to hex
-                    (
-                        format!("#{r:02X}{g:02X}{b:02X}"),
-                        json_theme.colors.short_round.clone(),
+        if duration.is_zero() {
+            0.0
+        } else {
+            self.pomodoro.current_session.current_time.as_secs_f32() / duration.as_secs_f32()
+        }
+    }
+
+    // The step of the custom sequence the current session is at, if any
+    fn current_step(&self) -> Option<SequenceStep> {
+        self.config
+            .with_active_profile()
+            .sequence_steps()
+            .into_iter()
+            .nth(self.pomodoro.current_session.step)
+    }
+
+    fn current_color(&self) -> Rgb {
+        // The color of the step comes first, then the one of the theme
+        let step_color = self
+            .current_step()
+            .and_then(|step| step.color)
+            .and_then(|color| Rgb::parse(&color));
+
+        step_color
+            .or_else(|| {
+                self.current_theme().and_then(|theme| {
+                    theme.color_for(
+                        self.pomodoro.current_session.session_type,
+                        self.progress(),
+                        self.config.gradient_interpolation,
                     )
-                }
-                _ => (
-                    json_theme.colors.focus_round.clone(),
-                    json_theme.colors.focus_round.clone(),
-                ),
-            },
-        };
+                })
+            })
+            .unwrap_or(Rgb {
+                red: 255,
+                green: 78,
+                blue: 77,
+            })
+    }
 
-        Theme {
-            colors: Colors {
-                accent: json_theme.colors.accent,
-                background: json_theme.colors.background,
-                background_light: json_theme.colors.background_light,
-                background_lightest: json_theme.colors.background_lightest,
-                focus_round: json_theme.colors.focus_round,
-                focus_round_middle,
-                focus_round_end,
-                foreground: json_theme.colors.foreground,
-                foreground_darker: json_theme.colors.foreground_darker,
-                foreground_darkest: json_theme.colors.foreground_darkest,
-                long_round: json_theme.colors.long_round,
-                short_round: json_theme.colors.short_round,
-            },
-            name: json_theme.name,
+    // The durations of the current session follow the new config
+    fn set_config(&mut self, config: Config) {
+        let pomodoro_config = pomodoro_config(&config);
+
+        if pomodoro_config.sequence != self.pomodoro.config.sequence {
+            // The current session may not be part of the new sequence
+            self.pomodoro = Pomodoro {
+                completed_focus_rounds: self.pomodoro.completed_focus_rounds,
+                ..Pomodoro::new(pomodoro_config)
+            };
+        } else {
+            self.pomodoro.config = pomodoro_config;
+        }
+        // A theme picked by the user replaces the one of the color scheme until it changes again
+        if config.theme != self.config.theme {
+            self.active_theme = config.theme.clone();
         }
+        self.config = config;
     }
-}
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-struct JsonColors {
-    #[serde(rename = "--color-accent")]
-    accent: String,
-    #[serde(rename = "--color-background")]
-    background: String,
-    #[serde(rename = "--color-background-light")]
-    background_light: String,
-    #[serde(rename = "--color-background-lightest")]
-    background_lightest: String,
-    #[serde(rename = "--color-focus-round")]
-    focus_round: String,
-    #[serde(rename = "--color-focus-round-middle")]
-    focus_round_middle: Option<String>,
-    #[serde(rename = "--color-focus-round-end")]
-    focus_round_end: Option<String>,
-    #[serde(rename = "--color-foreground")]
-    foreground: String,
-    #[serde(rename = "--color-foreground-darker")]
-    foreground_darker: String,
-    #[serde(rename = "--color-foreground-darkest")]
-    foreground_darkest: String,
-    #[serde(rename = "--color-long-round")]
-    long_round: String,
-    #[serde(rename = "--color-short-round")]
-    short_round: String,
-}
+    // Sends the state to the D-Bus service and the HTTP API, at the same time as to the webview
+    fn publish_state(&self) {
+        self.states
+            .json
+            .send_replace(serde_json::to_string(&self.pomodoro_state()).unwrap_or_default());
+
+        #[cfg(target_os = "linux")]
+        {
+            let timer_state = timer_state(self);
+            self.states.timer.send_if_modified(|current| {
+                let modified = *current != timer_state;
+                *current = timer_state;
+                modified
+            });
+        }
+    }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-struct JsonTheme {
-    colors: JsonColors,
-    name: String,
+    fn pomodoro_state(&self) -> PomodoroState {
+        PomodoroState {
+            color: self.current_color().to_hex(),
+            ended_sessions: self.ended_sessions,
+            pomodoro: self.pomodoro.to_unborrowed(),
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct ElmNotification {
-    body: String,
-    title: String,
-    red: u8,
-    green: u8,
-    blue: u8,
+// The pomodoro state sent to the front-end, with the color computed for the current theme
+#[derive(Clone, Debug, Serialize)]
+struct PomodoroState {
+    color: String,
+    // Lets the front-end notice the ends of the sessions, even between two of the same type
+    ended_sessions: u64,
+    #[serde(flatten)]
+    pomodoro: pomodoro::PomodoroUnborrowed,
 }
 
-fn get_config_file_path<R: Runtime>(
-    config_dir_name: &str,
-    path: &tauri::path::PathResolver<R>,
-) -> Result<PathBuf, tauri::Error> {
-    let config_dir = get_config_dir(config_dir_name, path)?;
-    Ok(Config::get_config_file_path(&config_dir, None))
+struct MenuStates<R: Runtime> {
+    // `None` is the entry without any profile
+    profile_menus: Vec<(Option<String>, tauri::menu::CheckMenuItem<R>)>,
+    toggle_visibility_menu: tauri::menu::MenuItem<R>,
+    toggle_play_menu: tauri::menu::MenuItem<R>,
+    undo_menu: tauri::menu::MenuItem<R>,
 }
 
-fn get_config_dir<R: Runtime>(
-    config_dir_name: &str,
-    path: &tauri::path::PathResolver<R>,
-) -> Result<PathBuf, tauri::Error> {
-    path.resolve(format!("{config_dir_name}/"), BaseDirectory::Config)
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct PlaySoundMessage {
+    sound_id: String,
+    quit_after_play: bool,
 }
 
-pub fn run_app<R: Runtime>(config_dir_name: &str, _builder: tauri::Builder<R>) {
-    let config_dir_name_owned = config_dir_name.to_string();
+#[derive(Clone, Debug, Serialize)]
+struct ThemeChangedMessage {
+    theme_name: String,
+}
 
+pub fn run_app<R: Runtime>(config_source: ConfigSource, _builder: tauri::Builder<R>) {
     tauri::Builder::default()
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_shell::init())
@@ -217,16 +229,73 @@ pub fn run_app<R: Runtime>(config_dir_name: &str, _builder: tauri::Builder<R>) {
 
             #[cfg(target_os = "macos")]
             app.set_activation_policy(tauri::ActivationPolicy::Accessory);
+
+            let config = match config_source.load() {
+                Ok(config) => config,
+                Err(e) => {
+                    // Tell the user what's wrong and quit once the message has been read,
+                    // the default config is only used until then
+                    let app_handle = app.handle().clone();
+                    app.dialog()
+                        .message(format!("Unable to load the config file: {e}"))
+                        .title("Pomodorolm")
+                        .kind(MessageDialogKind::Error)
+                        .show(move |_| app_handle.exit(1));
+
+                    Config::default()
+                }
+            };
+
             let quit = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
             let toggle_visibility =
                 MenuItemBuilder::with_id("toggle_visibility", "Hide").build(app)?;
             let skip = MenuItemBuilder::with_id("skip", "Skip").build(app)?;
+            let stop = MenuItemBuilder::with_id("stop", "Stop and take a break").build(app)?;
+            let extend_one_minute = MenuItemBuilder::with_id("extend:60", "+1 min").build(app)?;
+            let extend_five_minutes =
+                MenuItemBuilder::with_id("extend:300", "+5 min").build(app)?;
             let toggle_play = MenuItemBuilder::with_id("toggle_play", "Play").build(app)?;
+            // Enabled once there is something to undo
+            let undo = MenuItemBuilder::with_id("undo", "Undo")
+                .enabled(false)
+                .build(app)?;
+
+            let mut profile_menus = vec![];
+            let mut profiles_submenu = SubmenuBuilder::new(app, "Profile");
+            if !config.profiles.is_empty() {
+                for profile_name in
+                    std::iter::once(None).chain(config.profiles.keys().cloned().map(Some))
+                {
+                    let profile_menu = CheckMenuItemBuilder::with_id(
+                        format!("profile:{}", profile_name.as_deref().unwrap_or("")),
+                        profile_name.as_deref().unwrap_or("Default"),
+                    )
+                    .checked(profile_name == config.active_profile)
+                    .build(app)?;
 
-            let tray_menu = MenuBuilder::new(app)
-                .item(&skip)
+                    profiles_submenu = profiles_submenu.item(&profile_menu);
+                    profile_menus.push((profile_name, profile_menu));
+                }
+            }
+
+            let mut tray_menu_builder = MenuBuilder::new(app).item(&skip);
+            // Flow sessions can only be stopped in flow mode
+            if config.flow_mode {
+                tray_menu_builder = tray_menu_builder.item(&stop);
+            }
+            tray_menu_builder = tray_menu_builder
                 .item(&toggle_play)
-                .separator()
+                .item(&undo)
+                .item(&extend_one_minute)
+                .item(&extend_five_minutes)
+                .separator();
+            // Only show the profiles if there is something to choose from
+            if !profile_menus.is_empty() {
+                tray_menu_builder = tray_menu_builder
+                    .item(&profiles_submenu.build()?)
+                    .separator();
+            }
+            let tray_menu = tray_menu_builder
                 .item(&toggle_visibility)
                 .separator()
                 .item(&quit)
@@ -293,12 +362,34 @@ pub fn run_app<R: Runtime>(config_dir_name: &str, _builder: tauri::Builder<R>) {
                             };
                         }
                     }
-                    _ => (),
+                    id if id == "undo" || id == "stop" || id.starts_with("extend:") => {
+                        let app_handle = app.clone();
+                        let message = id.to_string();
+
+                        tauri::async_runtime::spawn(async move {
+                            let state: tauri::State<AppState> = app_handle.state();
+                            let mut app = state.0.lock().await;
+                            if let Err(e) = process_message(&app_handle, &mut app, &message).await {
+                                eprintln!("Unable to apply `{message}`: {e}.");
+                            }
+                        });
+                    }
+                    id => {
+                        if let Some(profile_name) = id.strip_prefix("profile:") {
+                            let profile_name =
+                                (!profile_name.is_empty()).then(|| profile_name.to_string());
+                            let app_handle = app.clone();
+
+                            tauri::async_runtime::spawn(async move {
+                                if let Err(e) = use_profile(&app_handle, profile_name).await {
+                                    eprintln!("Unable to switch profile: {e}.");
+                                }
+                            });
+                        }
+                    }
                 })
                 .build(app);
 
-            let config = read_config_from_disk(&config_dir_name_owned, app.path())?;
-
             if let Some(window) = app.get_webview_window("main") {
                 if config.start_minimized {
                     #[cfg(target_os = "macos")]
@@ -311,17 +402,50 @@ pub fn run_app<R: Runtime>(config_dir_name: &str, _builder: tauri::Builder<R>) {
                 let _ = window.set_always_on_top(config.always_on_top);
             }
 
-            let pomodoro = pomodoro_state_from_config(&config);
+            let today = Local::now().date_naive();
+            let pomodoro = Pomodoro {
+                // The rounds completed today before the app started count too
+                completed_focus_rounds: today_focus_count(&config_source, today)
+                    .try_into()
+                    .unwrap_or(u16::MAX),
+                ..pomodoro_state_from_config(&config)
+            };
+
+            let themes = load_themes(app.handle(), &config_source.themes_dir());
 
-            app.manage(AppState(Arc::new(Mutex::new(App {
+            let states = StateSenders {
+                json: watch::channel(String::new()).0,
+                #[cfg(target_os = "linux")]
+                timer: watch::channel(TimerState::of(
+                    &pomodoro,
+                    config.default_label(pomodoro.current_session.session_type),
+                ))
+                .0,
+            };
+            let app_state = App {
+                // Until the color scheme is known, see `load_init_data`
+                active_theme: config.theme.clone(),
                 config: config.clone(),
-                config_dir_name: config_dir_name_owned,
+                config_source: config_source.clone(),
+                ended: None,
+                ended_sessions: 0,
                 pomodoro,
-            }))));
+                states,
+                themes,
+                today,
+                undo_stack: UndoStack::default(),
+            };
+            app_state.publish_state();
+            app.manage(AppState(Arc::new(Mutex::new(app_state))));
+
+            #[cfg(target_os = "linux")]
+            app.manage(NotificationsState(Mutex::new(Notifications::default())));
 
             app.manage(AppMenuStates(std::sync::Mutex::new(MenuStates {
+                profile_menus,
                 toggle_visibility_menu: toggle_visibility,
                 toggle_play_menu: toggle_play,
+                undo_menu: undo,
             })));
 
             let sound_file_path = get_sound_file("audio-tick", app.handle(), &config)
@@ -329,6 +453,49 @@ pub fn run_app<R: Runtime>(config_dir_name: &str, _builder: tauri::Builder<R>) {
             let audio_path = sound_file_path.to_string_lossy();
 
             tauri::async_runtime::spawn(tick(app.handle().clone(), audio_path.to_string()));
+            tauri::async_runtime::spawn(watch_color_scheme(app.handle().clone()));
+            tauri::async_runtime::spawn(watch_idle(app.handle().clone()));
+            tauri::async_runtime::spawn(watch_screen_lock(app.handle().clone()));
+
+            if let Some(backend) = config.do_not_disturb {
+                match DesktopDoNotDisturb::new(backend) {
+                    Some(do_not_disturb) => {
+                        app.manage(DoNotDisturbState(Mutex::new(DoNotDisturbSwitch::new(
+                            do_not_disturb,
+                        ))));
+                    }
+                    None => eprintln!("No Do Not Disturb backend found for the current desktop."),
+                }
+            }
+            tauri::async_runtime::spawn(watch_config_file(app.handle().clone()));
+            #[cfg(unix)]
+            tauri::async_runtime::spawn(serve_control_socket(
+                app.handle().clone(),
+                config_source.control_socket(),
+            ));
+            #[cfg(target_os = "linux")]
+            tauri::async_runtime::spawn(serve_dbus(app.handle().clone()));
+            if let Some(port) = config.http_api_port {
+                match config
+                    .http_api_token
+                    .clone()
+                    .filter(|token| !token.is_empty())
+                {
+                    Some(token) => {
+                        tauri::async_runtime::spawn(serve_http_api(
+                            app.handle().clone(),
+                            port,
+                            http_api::Access {
+                                token,
+                                allowed_origins: config.http_api_allowed_origins.clone(),
+                            },
+                        ));
+                    }
+                    None => eprintln!(
+                        "Not serving the HTTP API on port {port}: `http_api_token` is not set."
+                    ),
+                }
+            }
 
             #[cfg(desktop)]
             {
@@ -344,20 +511,23 @@ pub fn run_app<R: Runtime>(config_dir_name: &str, _builder: tauri::Builder<R>) {
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
-            change_icon,
             close_window,
             handle_external_message,
             hide_window,
             load_init_data,
             minimize_window,
-            notify,
             play_sound_command,
             update_config,
             update_session_status,
             quit
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                restore_notifications(app_handle);
+            }
+        });
 }
 
 fn manage_autostart(
@@ -382,32 +552,37 @@ fn manage_autostart(
     Ok(())
 }
 
-fn read_config_from_disk<R: Runtime>(
-    config_dir_name: &str,
-    app_path: &tauri::path::PathResolver<R>,
-) -> Result<Config, Box<dyn std::error::Error>> {
-    let config_dir = get_config_dir(config_dir_name, app_path)?;
-
-    Config::get_or_create_from_disk(&config_dir, None)
-}
-
 fn pomodoro_config(config: &Config) -> pomodoro::Config {
+    let config = config.with_active_profile();
+
     pomodoro::Config {
-        auto_start_long_break_timer: config.auto_start_break_timer,
-        auto_start_short_break_timer: config.auto_start_break_timer,
+        auto_start_delay: config.auto_start_delay,
+        auto_start_long_break_timer: config.auto_start_long_break_timer,
+        auto_start_short_break_timer: config.auto_start_short_break_timer,
         auto_start_focus_timer: config.auto_start_work_timer,
+        daily_focus_target: config.daily_focus_target,
+        flow_break_ratio: config.flow_break_ratio,
+        flow_mode: config.flow_mode,
         focus_duration: config.focus_duration,
         long_break_duration: config.long_break_duration,
         max_focus_rounds: config.max_round_number,
+        max_session_duration: config.max_session_duration,
+        sequence: config
+            .sequence_steps()
+            .into_iter()
+            .map(|step| pomodoro::Step {
+                name: step.name,
+                session_type: step.kind,
+                duration: step.duration,
+                auto_start: step.auto_start,
+            })
+            .collect(),
         short_break_duration: config.short_break_duration,
     }
 }
 
 fn pomodoro_state_from_config(config: &Config) -> Pomodoro {
-    pomodoro::Pomodoro {
-        config: pomodoro_config(config),
-        ..pomodoro::Pomodoro::default()
-    }
+    Pomodoro::new(pomodoro_config(config))
 }
 
 fn get_themes_for_directory(themes_path: PathBuf) -> Vec<PathBuf> {
@@ -431,6 +606,29 @@ fn get_themes_for_directory(themes_path: PathBuf) -> Vec<PathBuf> {
     themes_paths_bufs
 }
 
+fn load_themes(app_handle: &AppHandle, custom_themes_path: &Path) -> Vec<Theme> {
+    let theme_resource_path = resolve_resource_path(app_handle, String::from("themes/"))
+        .expect("Unable to resolve `themes/{}` resource.");
+
+    let mut themes_paths: Vec<PathBuf> = get_themes_for_directory(theme_resource_path);
+
+    themes_paths.extend_from_slice(&get_themes_for_directory(custom_themes_path.to_path_buf()));
+
+    let mut themes: Vec<Theme> = vec![];
+
+    for path in themes_paths {
+        let file = fs::File::open(path.clone()).expect("file should open read only");
+        let loaded_theme: Result<JsonTheme, serde_json::Error> = serde_json::from_reader(file);
+
+        match loaded_theme {
+            Ok(theme) => themes.push(Theme::from(theme)),
+            Err(err) => eprintln!("Impossible to read JSON {}: {:?}", path.display(), err),
+        }
+    }
+
+    themes
+}
+
 fn should_play_tick_sound(config: &Config, pomodoro: &Pomodoro) -> bool {
     match (
         pomodoro.current_session.status,
@@ -450,7 +648,7 @@ fn should_play_tick_sound(config: &Config, pomodoro: &Pomodoro) -> bool {
 }
 
 async fn tick(app_handle: AppHandle, path: String) {
-    let mut stream = IntervalStream::new(time::interval(Duration::from_secs(1)));
+    let mut stream = IntervalStream::new(time::interval(pomodoro::TICK));
 
     match app_handle.get_webview_window("main") {
         Some(window) => {
@@ -462,9 +660,36 @@ async fn tick(app_handle: AppHandle, path: String) {
                 let play_tick: bool =
                     should_play_tick_sound(&state_guard.config, &state_guard.pomodoro);
 
-                state_guard.pomodoro = pomodoro::tick(&state_guard.pomodoro);
+                let today = Local::now().date_naive();
+                if today != state_guard.today {
+                    state_guard.pomodoro = pomodoro::new_day(&state_guard.pomodoro);
+                    state_guard.today = today;
+                }
 
-                let _ = window.emit("external-message", state_guard.pomodoro.to_unborrowed());
+                let previous = state_guard.pomodoro.clone();
+                let completed = pomodoro::is_last_tick(&previous);
+                state_guard.pomodoro = pomodoro::tick(&previous);
+                run_hooks(
+                    &state_guard,
+                    &previous,
+                    if completed {
+                        hooks::Change::Completed
+                    } else {
+                        hooks::Change::Other
+                    },
+                );
+
+                // Before the notification of the end of the session, for it not to be silenced
+                switch_do_not_disturb(&app_handle, &state_guard.pomodoro).await;
+                if completed {
+                    session_ended(&app_handle, &mut state_guard, &previous, true);
+                }
+                #[cfg(target_os = "linux")]
+                update_break_countdown(&app_handle, &state_guard);
+
+                let _ = window.emit("external-message", state_guard.pomodoro_state());
+                state_guard.publish_state();
+                update_tray_icon(&app_handle, &state_guard);
 
                 if play_tick {
                     let new_path = path.clone();
@@ -491,19 +716,295 @@ async fn tick(app_handle: AppHandle, path: String) {
     }
 }
 
-#[tauri::command]
-async fn change_icon<R: tauri::Runtime>(
-    app_handle: tauri::AppHandle<R>,
-    red: u8,
-    green: u8,
-    blue: u8,
-    fill_percentage: f32,
-    paused: bool,
-) -> Result<(), ()> {
+async fn watch_color_scheme(app_handle: AppHandle) {
+    color_scheme::watch(|color_scheme| {
+        let app_handle = app_handle.clone();
+
+        tauri::async_runtime::spawn(async move {
+            let state: tauri::State<AppState> = app_handle.state();
+            let mut state_guard = state.0.lock().await;
+
+            // Only switch if `theme_light`/`theme_dark` are set in the config
+            let Some(theme_name) = state_guard
+                .config
+                .theme_for_color_scheme(color_scheme)
+                .map(str::to_string)
+            else {
+                return;
+            };
+
+            state_guard.active_theme = theme_name.clone();
+            update_tray_icon(&app_handle, &state_guard);
+            if let Some(window) = app_handle.get_webview_window("main") {
+                let _ = window.emit("theme-changed", ThemeChangedMessage { theme_name });
+            }
+        });
+    })
+    .await
+}
+
+// Pauses the focus sessions while the user is away, see `idle_pause_after`
+async fn watch_idle(app_handle: AppHandle) {
+    let monitor = idle::DesktopIdleMonitor::new().await;
+    let mut watcher = IdleWatcher::default();
+    let mut stream = IntervalStream::new(time::interval(idle::POLL_INTERVAL));
+
+    while stream.next().await.is_some() {
+        let state: tauri::State<AppState> = app_handle.state();
+        let (pause_after, pomodoro) = {
+            let app = state.0.lock().await;
+            (app.config.idle_pause_after, app.pomodoro.clone())
+        };
+        let Some(pause_after) = pause_after else {
+            continue;
+        };
+
+        // The D-Bus call is made without holding the lock
+        let Some(event) = watcher.check(&monitor, pause_after, &pomodoro).await else {
+            continue;
+        };
+
+        let mut app = state.0.lock().await;
+        let result = match event {
+            IdleEvent::Away(_) => process_message(&app_handle, &mut app, "pause").await,
+            IdleEvent::Back(idle_time) => match app.config.idle_time_policy {
+                IdleTimePolicy::Keep => come_back(&app_handle, &mut app, idle_time, true).await,
+                IdleTimePolicy::Discard => come_back(&app_handle, &mut app, idle_time, false).await,
+                IdleTimePolicy::Ask => {
+                    let app_handle = app_handle.clone();
+                    app_handle
+                        .dialog()
+                        .message(format!(
+                            "You were away for {} of the focus session. Keep it as focus time?",
+                            duration::format(idle_time)
+                        ))
+                        .title("Welcome back")
+                        .buttons(MessageDialogButtons::OkCancelCustom(
+                            "Keep".to_string(),
+                            "Discard".to_string(),
+                        ))
+                        .show({
+                            let app_handle = app_handle.clone();
+                            move |keep| {
+                                tauri::async_runtime::spawn(async move {
+                                    let state: tauri::State<AppState> = app_handle.state();
+                                    let mut app = state.0.lock().await;
+                                    if let Err(e) =
+                                        come_back(&app_handle, &mut app, idle_time, keep).await
+                                    {
+                                        eprintln!("Unable to resume the session: {e}.");
+                                    }
+                                });
+                            }
+                        });
+                    Ok(())
+                }
+            },
+        };
+
+        if let Err(e) = result {
+            eprintln!("Unable to follow the idle time: {e}.");
+        }
+    }
+}
+
+// Resumes the session paused while the user was away, without the idle time unless `keep`
+async fn come_back<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    app: &mut App,
+    idle_time: Duration,
+    keep: bool,
+) -> Result<(), String> {
+    // The user may have done something else with the timer in the meantime
+    if app.pomodoro.current_session.status != SessionStatus::Paused {
+        return Ok(());
+    }
+
+    if !keep {
+        app.pomodoro = pomodoro::discard(&app.pomodoro, idle_time);
+    }
+    process_message(app_handle, app, "play").await
+}
+
+// Pauses or ends the focus sessions when the screen gets locked or the computer goes to sleep,
+// see `lock_action`
+async fn watch_screen_lock(app_handle: AppHandle) {
+    let mut source = DesktopEventSource::new().await;
+    let mut watcher = LockWatcher::default();
+
+    while let Some(event) = source.next_event().await {
+        let state: tauri::State<AppState> = app_handle.state();
+        let mut app = state.0.lock().await;
+
+        let Some(reaction) = watcher.update(event, app.config.lock_action, &app.pomodoro) else {
+            continue;
+        };
+
+        let session_type = app.pomodoro.current_session.session_type;
+        let (message, history_event) = match reaction {
+            Reaction::Pause => (
+                "pause",
+                Event::SessionInterrupted {
+                    session_type,
+                    cause: event,
+                },
+            ),
+            Reaction::End => (
+                "skip",
+                Event::SessionEnded {
+                    session_type,
+                    cause: event,
+                },
+            ),
+            Reaction::Resume => (
+                "play",
+                Event::SessionResumed {
+                    session_type,
+                    cause: event,
+                },
+            ),
+        };
+
+        if let Err(e) = process_message(&app_handle, &mut app, message).await {
+            eprintln!("Unable to follow the screen lock: {e}.");
+            continue;
+        }
+        // The user didn't skip the session, Undo shouldn't bring it back
+        if reaction == Reaction::End {
+            let current = app.pomodoro.clone();
+            app.undo_stack.undo(&current);
+            update_undo_menu(&app_handle, &app);
+        }
+        if let Err(e) = History::new(&app.config_source.history_file()).record(history_event) {
+            eprintln!("Unable to record the interruption in the history: {e}");
+        }
+    }
+}
+
+// Silences the notifications of the other apps during the focus sessions of `pomodoro`, and
+// brings them back otherwise, see `do_not_disturb`. Called on every transition of the timer.
+async fn switch_do_not_disturb<R: Runtime>(app_handle: &AppHandle<R>, pomodoro: &Pomodoro) {
+    let Some(do_not_disturb) = app_handle.try_state::<DoNotDisturbState>() else {
+        return;
+    };
+
+    if let Err(e) = do_not_disturb.0.lock().await.update(pomodoro).await {
+        eprintln!("Unable to switch Do Not Disturb: {e}.");
+    }
+}
+
+// Brings the notifications back if the app quits during a focus session
+fn restore_notifications(app_handle: &AppHandle) {
+    let Some(do_not_disturb) = app_handle.try_state::<DoNotDisturbState>() else {
+        return;
+    };
+
+    let result =
+        tauri::async_runtime::block_on(async { do_not_disturb.0.lock().await.restore().await });
+    if let Err(e) = result {
+        eprintln!("Unable to bring the notifications back: {e}.");
+    }
+}
+
+// Picks up the changes made to the config file outside of the app, by `pomodorolm profile use`
+// for example
+async fn watch_config_file(app_handle: AppHandle) {
+    let config_source = {
+        let state: tauri::State<AppState> = app_handle.state();
+        let state_guard = state.0.lock().await;
+
+        state_guard.config_source.clone()
+    };
+
+    let modified_at = || {
+        fs::metadata(&config_source.config_file)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+
+    let mut last_modified_at = modified_at();
+    let mut stream = IntervalStream::new(time::interval(Duration::from_secs(2)));
+
+    while stream.next().await.is_some() {
+        let modified_at = modified_at();
+        if modified_at == last_modified_at {
+            continue;
+        }
+        last_modified_at = modified_at;
+
+        let config = fs::read_to_string(&config_source.config_file)
+            .map_err(ConfigError::from)
+            .and_then(|toml_str| Config::parse(&toml_str))
+            .and_then(|(config, _)| config_source.overrides.apply(&config))
+            .and_then(|config| config.validate().map(|_| config));
+
+        let state: tauri::State<AppState> = app_handle.state();
+        let mut state_guard = state.0.lock().await;
+
+        match config {
+            // Our own writes don't change anything
+            Ok(config) if config == state_guard.config => {}
+            Ok(config) => {
+                state_guard.set_config(config);
+                update_tray_icon(&app_handle, &state_guard);
+                notify_config_changed(&app_handle, &state_guard);
+            }
+            Err(e) => eprintln!("Ignoring the changes made to the config file: {e}."),
+        }
+    }
+}
+
+async fn use_profile<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    profile_name: Option<String>,
+) -> Result<(), ConfigError> {
+    let state: tauri::State<AppState> = app_handle.state();
+    let mut state_guard = state.0.lock().await;
+
+    let config = Config {
+        active_profile: profile_name,
+        ..state_guard.config.clone()
+    };
+
+    state_guard.config_source.save(&config)?;
+
+    state_guard.set_config(config);
+    update_tray_icon(app_handle, &state_guard);
+    notify_config_changed(app_handle, &state_guard);
+
+    Ok(())
+}
+
+// Sends the config to the front-end and updates the tray menu
+fn notify_config_changed<R: Runtime>(app_handle: &AppHandle<R>, app: &App) {
+    let state: tauri::State<'_, AppMenuStates<R>> = app_handle.state();
+    match state.0.lock() {
+        Ok(guard) => {
+            for (profile_name, profile_menu) in &guard.profile_menus {
+                if let Err(e) = profile_menu.set_checked(*profile_name == app.config.active_profile)
+                {
+                    eprintln!("Error checking MenuItem: {e:?}.");
+                }
+            }
+        }
+        Err(e) => eprintln!("Error getting state lock: {e:?}."),
+    };
+
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.emit(
+            "config-changed",
+            app.config.with_active_profile().to_front_end(),
+        );
+    }
+}
+
+fn update_tray_icon<R: tauri::Runtime>(app_handle: &tauri::AppHandle<R>, app: &App) {
     // Image dimensions
     let width = 512;
     let height = 512;
 
+    let Rgb { red, green, blue } = app.current_color();
+
     match app_handle.path().app_data_dir() {
         Ok(data_dir) => {
             match icon::create_icon(
@@ -513,8 +1014,8 @@ async fn change_icon<R: tauri::Runtime>(
                     red,
                     green,
                     blue,
-                    fill_percentage,
-                    paused,
+                    fill_percentage: 1.0 - app.progress(),
+                    paused: app.pomodoro.current_session.status == SessionStatus::Paused,
                 },
                 format!("{}/temp_icon_tray.png", data_dir.to_string_lossy()).as_str(),
             ) {
@@ -541,8 +1042,6 @@ async fn change_icon<R: tauri::Runtime>(
         }
         Err(e) => eprintln!("Unable to get app_data_dir for icon: {e:?}."),
     }
-
-    Ok(())
 }
 
 #[tauri::command]
@@ -578,94 +1077,61 @@ async fn update_config(
     state: tauri::State<'_, AppState>,
     app_handle: tauri::AppHandle,
     config: Config,
-) -> Result<pomodoro::PomodoroUnborrowed, ()> {
+) -> Result<PomodoroState, ConfigError> {
     let mut state_guard = state.0.lock().await;
 
-    match get_config_file_path(&state_guard.config_dir_name, app_handle.path()) {
-        Ok(config_file_pathbuf) => {
-            let config_file_path = config_file_pathbuf.to_string_lossy().to_string();
-
-            let file = OpenOptions::new()
-                .read(true)
-                .write(true)
-                .create(true)
-                .truncate(true)
-                .open(config_file_path);
-
-            // FIX: We should not fail silently and we should hanlde the errors properly
-            let _: Result<(), _> = match file {
-                Ok(mut f) => {
-                    let _ = f.write_all(toml::to_string(&config).unwrap().as_bytes());
-
-                    *state_guard = App {
-                        config: config.clone(),
-                        config_dir_name: state_guard.config_dir_name.clone(),
-                        pomodoro: pomodoro::Pomodoro {
-                            config: pomodoro_config(&config),
-                            ..state_guard.pomodoro.clone()
-                        },
-                    };
-
-                    // Manage autostart status
-                    let _ = manage_autostart(&app_handle, config.system_startup_auto_start);
-
-                    if let Some(window) = app_handle.get_webview_window("main") {
-                        let _ = window.set_always_on_top(config.always_on_top);
-                    }
-
-                    Ok::<(), ()>(())
-                }
-                Err(_) => {
-                    println!("Error opening config file on update");
-                    Ok(())
-                }
-            };
-        }
-        Err(e) => eprintln!("Unable to get config file path: {e:?}."),
-    }
-
-    Ok(state_guard.pomodoro.to_unborrowed())
-}
+    // The front-end edits the config with the active profile applied
+    let config = state_guard.config.apply_changes(config);
+    let profile_changed = config.active_profile != state_guard.config.active_profile;
 
-#[tauri::command]
-async fn load_init_data(
-    state: tauri::State<'_, AppState>,
-    app_handle: tauri::AppHandle,
-) -> Result<(Config, Vec<Theme>, pomodoro::PomodoroUnborrowed), ()> {
-    let state_guard = state.0.lock().await;
+    // The state is only updated once the config has been validated and saved
+    state_guard.config_source.save(&config)?;
 
-    let config_dir =
-        get_config_dir(&state_guard.config_dir_name, app_handle.path()).map_err(|_| ())?;
+    state_guard.set_config(config.clone());
 
-    let config = Config::get_or_create_from_disk(&config_dir, None).map_err(|_| ());
+    update_tray_icon(&app_handle, &state_guard);
 
-    let theme_resource_path = resolve_resource_path(&app_handle, String::from("themes/"))
-        .expect("Unable to resolve `themes/{}` resource.");
-
-    let mut themes_paths: Vec<PathBuf> = get_themes_for_directory(theme_resource_path);
+    if profile_changed {
+        notify_config_changed(&app_handle, &state_guard);
+    }
 
-    let custom_themes_path = app_handle.path().resolve(
-        format!("{}/themes/", state_guard.config_dir_name),
-        BaseDirectory::Config,
-    );
+    // Manage autostart status
+    let _ = manage_autostart(&app_handle, config.system_startup_auto_start);
 
-    if let Ok(path) = custom_themes_path {
-        themes_paths.extend_from_slice(&get_themes_for_directory(path));
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.set_always_on_top(config.always_on_top);
     }
 
-    let mut themes: Vec<Theme> = vec![];
-
-    for path in themes_paths {
-        let file = fs::File::open(path.clone()).expect("file should open read only");
-        let loaded_theme: Result<JsonTheme, serde_json::Error> = serde_json::from_reader(file);
+    Ok(state_guard.pomodoro_state())
+}
 
-        match loaded_theme {
-            Ok(theme) => themes.push(Theme::from(theme)),
-            Err(err) => eprintln!("Impossible to read JSON {}: {:?}", path.display(), err),
-        }
-    }
+#[tauri::command]
+async fn load_init_data(
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(serde_json::Value, Vec<Theme>, PomodoroState, String), ()> {
+    let mut state_guard = state.0.lock().await;
 
-    config.map(|c| (c, themes, state_guard.pomodoro.to_unborrowed()))
+    let config = state_guard.config_source.load().map_err(|_| ());
+    let color_scheme = color_scheme::current().await;
+
+    // Reload the themes to pick up the custom ones added since the app started
+    state_guard.themes = load_themes(&app_handle, &state_guard.config_source.themes_dir());
+
+    let config = config?;
+    // Start with the theme matching the desktop color scheme if automatic switching is set,
+    // the config keeps the one chosen by the user
+    state_guard.active_theme = config
+        .theme_for_color_scheme(color_scheme)
+        .map_or(config.theme.clone(), str::to_string);
+    update_tray_icon(&app_handle, &state_guard);
+
+    Ok((
+        config.with_active_profile().to_front_end(),
+        state_guard.themes.clone(),
+        state_guard.pomodoro_state(),
+        state_guard.active_theme.clone(),
+    ))
 }
 
 #[tauri::command]
@@ -681,11 +1147,24 @@ async fn play_sound_command(app_handle: tauri::AppHandle, play_sound_message: Pl
             v as f32 / 100.0
         });
 
-    match get_sound_file(
-        play_sound_message.sound_id.as_str(),
-        &app_handle,
-        &state_guard.config,
-    ) {
+    // The steps of a custom sequence can have their own sound
+    let step_audio = state_guard
+        .current_step()
+        .and_then(|step| step.audio)
+        .filter(|_| {
+            matches!(
+                play_sound_message.sound_id.as_str(),
+                "audio-work" | "audio-short-break" | "audio-long-break"
+            )
+        });
+
+    match step_audio.map(PathBuf::from).or_else(|| {
+        get_sound_file(
+            play_sound_message.sound_id.as_str(),
+            &app_handle,
+            &state_guard.config.with_active_profile(),
+        )
+    }) {
         Some(sound_file) => {
             // Fail silently if we can't play sound file
 
@@ -767,72 +1246,679 @@ async fn close_window(app_handle: tauri::AppHandle) {
     window.close().expect("failed to close window");
 }
 
-#[tauri::command]
-async fn notify(app_handle: tauri::AppHandle, notification: ElmNotification) {
-    let data_dir = app_handle.path().app_data_dir().unwrap();
+// The icon of the notifications, in the color of the session
+fn notification_icon<R: Runtime>(app_handle: &AppHandle<R>, color: Rgb) -> Option<String> {
+    let data_dir = match app_handle.path().app_data_dir() {
+        Ok(data_dir) => data_dir,
+        Err(e) => {
+            eprintln!("Unable to get app_data_dir for icon: {e:?}.");
+            return None;
+        }
+    };
+
     match icon::create_icon(
         icon::PomodorolmIcon {
             width: 512,
             height: 512,
-            red: notification.red,
-            green: notification.green,
-            blue: notification.blue,
+            red: color.red,
+            green: color.green,
+            blue: color.blue,
             fill_percentage: 1_f32,
             paused: false,
         },
         format!("{}/temp_icon_notification.png", data_dir.to_string_lossy()).as_str(),
     ) {
-        Ok(icon_path_buf) => {
-            // shows a notification with the given title and body
-            if app_handle.notification().permission_state().unwrap() == PermissionState::Granted {
-                let _ = app_handle
-                    .notification()
-                    .builder()
-                    .title(notification.title)
-                    .body(notification.body)
-                    .icon(icon_path_buf.to_string_lossy())
-                    .show();
+        Ok(icon_path_buf) => Some(icon_path_buf.to_string_lossy().to_string()),
+        Err(e) => {
+            eprintln!("Unable to create the notification icon: {e:?}.");
+            None
+        }
+    }
+}
+
+// Shows a notification, the actions, the urgency, the timeout and the replacement of the
+// previous notification are only supported on Linux
+fn show_notification<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    notification: DesktopNotification,
+    replace: bool,
+) {
+    #[cfg(target_os = "linux")]
+    {
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let notifications: tauri::State<NotificationsState> = app_handle.state();
+            let sent = notifications
+                .0
+                .lock()
+                .await
+                .send(&notification, replace)
+                .await;
+
+            match sent {
+                Ok((sent, sent_count)) => {
+                    let id = sent.id;
+                    match sent.action().await {
+                        Ok(Some(action)) => {
+                            // A replaced notification keeps its id, only the last one can act
+                            let replaced = {
+                                let notifications = notifications.0.lock().await;
+                                notifications.last_id == id
+                                    && notifications.sent_count != sent_count
+                            };
+                            if !replaced {
+                                run_notification_action(&app_handle, action).await;
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(e) => eprintln!("Unable to get the notification action: {e}."),
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Unable to show the notification through D-Bus: {e}.");
+                    show_plugin_notification(&app_handle, notification);
+                }
+            }
+        });
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = replace;
+        show_plugin_notification(app_handle, notification);
+    }
+}
+
+fn show_plugin_notification<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    notification: DesktopNotification,
+) {
+    if app_handle.notification().permission_state().ok() == Some(PermissionState::Granted) {
+        let _ = app_handle
+            .notification()
+            .builder()
+            .title(notification.title)
+            .body(notification.body)
+            .icon(notification.icon)
+            .show();
+    }
+}
+
+// The notifications shown through D-Bus, to replace or close them
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+struct Notifications {
+    connection: Option<zbus::Connection>,
+    // The last notification of a session end, and the number of them sent so far to know if it
+    // has been replaced
+    last_id: u32,
+    sent_count: u64,
+    // The countdown of the current break, 0 if there is none
+    countdown_id: u32,
+    // The text of the countdown last shown, not to show it again if it didn't change since some
+    // servers show the banner again on every update
+    countdown_body: String,
+    // The countdown was closed by the user, and isn't shown again until the next break
+    countdown_dismissed: bool,
+}
+
+#[cfg(target_os = "linux")]
+struct NotificationsState(Mutex<Notifications>);
+
+#[cfg(target_os = "linux")]
+impl Notifications {
+    async fn connection(&mut self) -> zbus::Result<zbus::Connection> {
+        if let Some(connection) = &self.connection {
+            return Ok(connection.clone());
+        }
+
+        let connection = zbus::Connection::session().await?;
+        self.connection = Some(connection.clone());
+        Ok(connection)
+    }
+
+    async fn send(
+        &mut self,
+        notification: &DesktopNotification,
+        replace: bool,
+    ) -> zbus::Result<(freedesktop::SentNotification, u64)> {
+        let connection = self.connection().await?;
+        let sent = freedesktop::send(
+            &connection,
+            notification,
+            if replace { self.last_id } else { 0 },
+        )
+        .await?;
+
+        self.last_id = sent.id;
+        self.sent_count += 1;
+        Ok((sent, self.sent_count))
+    }
+
+    // Shows or updates the countdown, `None` closes it. Gives the countdown when it is first
+    // shown, to know when it gets closed.
+    async fn update_countdown(
+        &mut self,
+        countdown: Option<DesktopNotification>,
+    ) -> zbus::Result<Option<freedesktop::SentNotification>> {
+        match countdown {
+            Some(countdown)
+                if self.countdown_dismissed || countdown.body == self.countdown_body =>
+            {
+                Ok(None)
+            }
+            Some(countdown) if self.countdown_id == 0 => {
+                let connection = self.connection().await?;
+                let sent = freedesktop::send(&connection, &countdown, 0).await?;
+                self.countdown_id = sent.id;
+                self.countdown_body = countdown.body;
+                Ok(Some(sent))
+            }
+            Some(countdown) => {
+                let connection = self.connection().await?;
+                self.countdown_id =
+                    freedesktop::notify(&connection, &countdown, self.countdown_id).await?;
+                self.countdown_body = countdown.body;
+                Ok(None)
+            }
+            None => {
+                self.countdown_body.clear();
+                self.countdown_dismissed = false;
+                if self.countdown_id != 0 {
+                    let connection = self.connection().await?;
+                    let id = std::mem::take(&mut self.countdown_id);
+                    freedesktop::close(&connection, id).await?;
+                }
+                Ok(None)
             }
         }
-        Err(e) => eprintln!("Unable to get app_data_dir for icon: {e:?}."),
+    }
+
+    // The countdown `id` was closed, by the user unless `update_countdown` closed it already
+    async fn countdown_closed(&mut self, id: u32) -> zbus::Result<()> {
+        if self.countdown_id == 0 {
+            return Ok(());
+        }
+
+        self.countdown_dismissed = true;
+        // Updated in the meantime, the server showed it again with another id
+        let shown_again = std::mem::take(&mut self.countdown_id);
+        if shown_again != id {
+            let connection = self.connection().await?;
+            freedesktop::close(&connection, shown_again).await?;
+        }
+        Ok(())
+    }
+}
+
+// Keeps the countdown notification of the current break up to date, if it's enabled
+#[cfg(target_os = "linux")]
+fn update_break_countdown<R: Runtime>(app_handle: &AppHandle<R>, app: &App) {
+    let session = &app.pomodoro.current_session;
+    let countdown = (app.config.break_countdown_notification
+        && session.session_type != SessionType::Focus
+        && session.status != SessionStatus::NotStarted)
+        .then(|| {
+            let remaining = app
+                .pomodoro
+                .duration_of_session(session)
+                .saturating_sub(session.current_time);
+
+            DesktopNotification {
+                title: format!(
+                    "{} in progress",
+                    session
+                        .label
+                        .as_deref()
+                        .unwrap_or(app.config.default_label(session.session_type))
+                ),
+                body: if session.status == SessionStatus::Paused {
+                    format!("Paused, {} left", notification::countdown(remaining))
+                } else {
+                    format!("{} left", notification::countdown(remaining))
+                },
+                icon: String::new(),
+                actions: vec![],
+                urgency: Urgency::Low,
+                timeout: Some(Duration::ZERO),
+            }
+        });
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let notifications: tauri::State<NotificationsState> = app_handle.state();
+        let shown = notifications
+            .0
+            .lock()
+            .await
+            .update_countdown(countdown)
+            .await;
+
+        match shown {
+            // The countdown has no actions, it only waits to be closed
+            Ok(Some(sent)) => {
+                let id = sent.id;
+                let closed = match sent.action().await {
+                    Ok(_) => notifications.0.lock().await.countdown_closed(id).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = closed {
+                    eprintln!("Unable to follow the break countdown: {e}.");
+                }
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Unable to update the break countdown: {e}."),
+        }
+    });
+}
+
+#[cfg(target_os = "linux")]
+async fn run_notification_action<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    action: NotificationAction,
+) {
+    let state: tauri::State<AppState> = app_handle.state();
+
+    let result = match action {
+        NotificationAction::Extend => {
+            let mut app = state.0.lock().await;
+            let extend_duration = app.config.extend_duration;
+            extend_session(&mut app, extend_duration, true).map(|()| {
+                update_tray_icon(app_handle, &app);
+                let _ = app_handle.emit("external-message", app.pomodoro_state());
+                app.publish_state();
+            })
+        }
+        NotificationAction::Snooze => {
+            let (session, snooze_duration) = {
+                let mut app = state.0.lock().await;
+                if app.pomodoro.current_session.status == SessionStatus::Running
+                    && let Err(e) = process_message(app_handle, &mut app, "pause").await
+                {
+                    eprintln!("Unable to snooze the session: {e}.");
+                }
+                (
+                    app.pomodoro.current_session.clone(),
+                    app.config.snooze_duration,
+                )
+            };
+
+            time::sleep(snooze_duration).await;
+
+            let mut app = state.0.lock().await;
+            // Only if nothing happened to the session in the meantime
+            if app.pomodoro.current_session == session {
+                process_message(app_handle, &mut app, "play").await
+            } else {
+                Ok(())
+            }
+        }
+        action => {
+            let mut app = state.0.lock().await;
+            process_message(app_handle, &mut app, action.key()).await
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("Unable to run the notification action: {e}.");
     }
 }
 
+// Adds up to `time` to the current session, or to the session that just ended if `reopen`
+fn extend_session(app: &mut App, time: Duration, reopen: bool) -> Result<(), String> {
+    let (pomodoro, extended) = match (reopen, &app.ended) {
+        (false, _) => (&app.pomodoro, pomodoro::extend(&app.pomodoro, time)),
+        (true, Some(ended)) => (ended, pomodoro::reopen(ended, time)),
+        (true, None) => return Err("the last session can't be reopened anymore".to_string()),
+    };
+
+    let added = extended.current_session.extra_time - pomodoro.current_session.extra_time;
+    // `app.ended` is kept, to try again with the other actions of the notification
+    if added.is_zero() {
+        return Err(format!(
+            "the session is already {} long (`max_session_duration`)",
+            duration::format(app.config.max_session_duration)
+        ));
+    }
+
+    if let Err(e) = History::new(&app.config_source.history_file()).record(Event::SessionExtended {
+        session_type: extended.current_session.session_type,
+        seconds: added.as_secs(),
+        reopened: reopen,
+    }) {
+        eprintln!("Unable to record the extension in the history: {e}");
+    }
+
+    if reopen {
+        app.ended = None;
+    }
+    app.pomodoro = extended;
+    Ok(())
+}
+
+// Runs the hooks of the config for what happened between `previous` and `app.pomodoro`, one
+// after the other in the background
+fn run_hooks(app: &App, previous: &Pomodoro, change: hooks::Change) {
+    let hooks = &app.config.hooks;
+    let to_run: Vec<_> = hooks::events(previous, &app.pomodoro, change)
+        .into_iter()
+        .filter_map(|data| Some((hooks.command(data.event)?.to_string(), data)))
+        .collect();
+    if to_run.is_empty() {
+        return;
+    }
+
+    let timeout = hooks.timeout;
+    tauri::async_runtime::spawn(async move {
+        for (command, data) in to_run {
+            if let Err(e) = hooks::run(&command, &data, timeout).await {
+                eprintln!("Unable to run the hook: {e}.");
+            }
+        }
+    });
+}
+
+// Called when `app.pomodoro` moved on from `previous`, `completed` is false if the session was
+// skipped
+fn session_ended<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    app: &mut App,
+    previous: &Pomodoro,
+    completed: bool,
+) {
+    let history = History::new(&app.config_source.history_file());
+    let ended_session = &previous.current_session;
+    app.ended_sessions += 1;
+    let flow = previous.is_flow_session(ended_session);
+
+    // A completed session can be reopened to extend it, a flow session was already as long as
+    // wanted
+    app.ended = (completed && !flow).then(|| previous.clone());
+
+    if completed
+        && let Err(e) = history.record(Event::SessionCompleted {
+            session_type: ended_session.session_type,
+            duration: if flow {
+                ended_session.current_time
+            } else {
+                previous.duration_of_session(ended_session)
+            }
+            .as_secs(),
+            label: ended_session.label.clone(),
+        })
+    {
+        eprintln!("Unable to record the session in the history: {e}");
+    }
+
+    let event = if app.pomodoro.is_done_for_the_day() && !previous.is_done_for_the_day() {
+        NotificationEvent::DailyTargetReached
+    } else {
+        NotificationEvent::between(
+            ended_session.session_type,
+            app.pomodoro.current_session.session_type,
+        )
+    };
+
+    if !app.config.desktop_notifications {
+        return;
+    }
+
+    let today_count = today_focus_count(&app.config_source, Local::now().date_naive());
+
+    let (title, body) =
+        app.config
+            .notification_templates
+            .template(event)
+            .render(&NotificationContext {
+                label: ended_session.label.clone().unwrap_or_else(|| {
+                    app.config
+                        .default_label(ended_session.session_type)
+                        .to_string()
+                }),
+                next_session: app.pomodoro.current_session.session_type,
+                duration: app
+                    .pomodoro
+                    .duration_of_session(&app.pomodoro.current_session),
+                round: previous.current_work_round_number,
+                today_count,
+            });
+
+    let actions = notification::actions(
+        app.pomodoro.current_session.session_type,
+        app.pomodoro.current_session.status == SessionStatus::Running,
+        app.config.snooze_duration,
+        app.ended.is_some().then_some(app.config.extend_duration),
+    );
+
+    show_notification(
+        app_handle,
+        DesktopNotification {
+            title,
+            body,
+            icon: notification_icon(app_handle, app.current_color()).unwrap_or_default(),
+            actions,
+            urgency: app.config.notification_urgency,
+            timeout: app.config.notification_timeout,
+        },
+        app.config.replace_notifications,
+    );
+}
+
+// Focus sessions of the history completed on `day`
+fn today_focus_count(config_source: &ConfigSource, day: NaiveDate) -> usize {
+    History::new(&config_source.history_file())
+        .completed_focus_count(day)
+        .unwrap_or_else(|e| {
+            eprintln!("Unable to read the history: {e}");
+            0
+        })
+}
+
 #[tauri::command]
 async fn handle_external_message<R: tauri::Runtime>(
     app: tauri::AppHandle<R>,
     state: tauri::State<'_, AppState>,
     name: String,
-) -> Result<pomodoro::PomodoroUnborrowed, ()> {
+) -> Result<PomodoroState, ()> {
     let mut app_state_guard = state.0.lock().await;
 
-    match name.as_str() {
+    if let Err(e) = apply_message(&app, &mut app_state_guard, &name).await {
+        eprintln!("[rust] {e}, ignoring.");
+    }
+
+    // Needed because Tauri doesn't play well with returning references
+    // with async commands
+    // https://v2.tauri.app/develop/calling-rust/#async-commands
+    Ok(app_state_guard.pomodoro_state())
+}
+
+// The state transitions asked by the front-end, the tray, the notifications or the command
+// line
+async fn apply_message<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    app: &mut App,
+    name: &str,
+) -> Result<(), String> {
+    // The last session can only be reopened if nothing happened since it ended
+    app.ended = None;
+    let before = app.pomodoro.clone();
+
+    match name {
         "pause" => {
-            app_state_guard.pomodoro = pomodoro::pause(&app_state_guard.pomodoro);
+            app.pomodoro = pomodoro::pause(&app.pomodoro);
         }
         "play" => {
-            app_state_guard.pomodoro = pomodoro::play(&app_state_guard.pomodoro);
+            app.pomodoro = pomodoro::play(&app.pomodoro);
         }
         "quit" => {
-            app.exit(0);
+            app_handle.exit(0);
         }
         "reset_round" => {
-            app_state_guard.pomodoro = pomodoro::reset_round(&app_state_guard.pomodoro);
+            app.undo_stack.push(&app.pomodoro);
+            app.pomodoro = pomodoro::reset_round(&app.pomodoro);
         }
         "reset_session" => {
-            app_state_guard.pomodoro = pomodoro::reset_session(&app_state_guard.pomodoro);
+            app.undo_stack.push(&app.pomodoro);
+            app.pomodoro = pomodoro::reset_session(&app.pomodoro);
         }
 
         "skip" => {
-            app_state_guard.pomodoro = pomodoro::next(&app_state_guard.pomodoro);
+            let previous = app.pomodoro.clone();
+            app.undo_stack.push(&previous);
+            app.pomodoro = pomodoro::next(&previous);
+            switch_do_not_disturb(app_handle, &app.pomodoro).await;
+            session_ended(app_handle, app, &previous, false);
         }
-        message => eprintln!("[rust] Got unknown message `{message}`, ignoring."),
+        "stop" => {
+            let previous = app.pomodoro.clone();
+            let Some(stopped) = pomodoro::stop(&previous) else {
+                return Err("there is no flow session to stop".to_string());
+            };
+
+            app.undo_stack.push(&previous);
+            app.pomodoro = stopped;
+            switch_do_not_disturb(app_handle, &app.pomodoro).await;
+            session_ended(app_handle, app, &previous, true);
+        }
+        "undo" => match app.undo_stack.undo(&app.pomodoro) {
+            Some(pomodoro) => app.pomodoro = pomodoro,
+            None => return Err("nothing to undo".to_string()),
+        },
+        message => match message.strip_prefix("extend:").and_then(duration::parse) {
+            Some(time) => extend_session(app, time, false)?,
+            None => return Err(format!("unknown message `{message}`")),
+        },
     }
 
-    // Needed because Tauri doesn't play well with returning references
-    // with async commands
-    // https://v2.tauri.app/develop/calling-rust/#async-commands
-    Ok(app_state_guard.pomodoro.to_unborrowed())
+    switch_do_not_disturb(app_handle, &app.pomodoro).await;
+
+    let change = match name {
+        "skip" => hooks::Change::Skipped,
+        "stop" => hooks::Change::Completed,
+        "reset_round" | "reset_session" | "undo" => hooks::Change::Reset,
+        _ => hooks::Change::Other,
+    };
+    run_hooks(app, &before, change);
+    update_tray_icon(app_handle, app);
+    update_undo_menu(app_handle, app);
+    app.publish_state();
+
+    Ok(())
+}
+
+fn update_undo_menu<R: Runtime>(app_handle: &AppHandle<R>, app: &App) {
+    let state: tauri::State<'_, AppMenuStates<R>> = app_handle.state();
+
+    match state.0.lock() {
+        Ok(guard) => {
+            if let Err(e) = guard.undo_menu.set_enabled(!app.undo_stack.is_empty()) {
+                eprintln!("Error enabling the undo MenuItem: {e:?}.");
+            }
+        }
+        Err(e) => eprintln!("Error getting state lock: {e:?}."),
+    }
+}
+
+// Same as `apply_message`, for the messages that don't come from the front-end which has to be
+// told about the new state
+async fn process_message<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    app: &mut App,
+    name: &str,
+) -> Result<(), String> {
+    apply_message(app_handle, app, name).await?;
+
+    let _ = app_handle.emit("external-message", app.pomodoro_state());
+
+    Ok(())
+}
+
+// Lets the command line control the app
+#[cfg(unix)]
+async fn serve_control_socket(app_handle: AppHandle, path: PathBuf) {
+    let result = control::serve(&path, move |message| {
+        let app_handle = app_handle.clone();
+        async move {
+            let state: tauri::State<AppState> = app_handle.state();
+            let mut app = state.0.lock().await;
+            process_message(&app_handle, &mut app, &message).await
+        }
+    })
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("Unable to listen to the command line on {path:?}: {e}.");
+    }
+}
+
+// Lets the desktop extensions and the scripts follow and control the timer over D-Bus
+#[cfg(target_os = "linux")]
+async fn serve_dbus(app_handle: AppHandle) {
+    let states = {
+        let state: tauri::State<AppState> = app_handle.state();
+        state.0.lock().await.states.timer.subscribe()
+    };
+
+    let result = dbus_service::serve(
+        move |message| {
+            let app_handle = app_handle.clone();
+            async move {
+                let state: tauri::State<AppState> = app_handle.state();
+                let mut app = state.0.lock().await;
+                process_message(&app_handle, &mut app, &message).await
+            }
+        },
+        states,
+    )
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("Unable to serve the timer on D-Bus: {e}.");
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn timer_state(app: &App) -> TimerState {
+    TimerState::of(
+        &app.pomodoro,
+        app.config
+            .default_label(app.pomodoro.current_session.session_type),
+    )
+}
+
+// Lets the Stream Deck setups, the OBS overlays and the dashboards follow and control the timer
+// over HTTP, see `http_api_port`
+async fn serve_http_api(app_handle: AppHandle, port: u16, access: http_api::Access) {
+    let listener = match http_api::bind(port).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Unable to serve the HTTP API on port {port}: {e}.");
+            return;
+        }
+    };
+
+    let states = {
+        let state: tauri::State<AppState> = app_handle.state();
+        state.0.lock().await.states.json.subscribe()
+    };
+
+    let result = http_api::serve(
+        listener,
+        access,
+        move |message| {
+            let app_handle = app_handle.clone();
+            async move {
+                let state: tauri::State<AppState> = app_handle.state();
+                let mut app = state.0.lock().await;
+                process_message(&app_handle, &mut app, &message).await
+            }
+        },
+        states,
+    )
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("Unable to serve the HTTP API on port {port}: {e}.");
+    }
 }
 
 fn resolve_resource_path(
@@ -886,6 +1972,12 @@ fn get_sound_file<'a>(
             Some(path) => Some(PathBuf::from(path)),
             None => resolve_resource_path(app_handle, format!("audio/{}", "alert-work.mp3")).ok(),
         },
+        "audio-daily-target" => match &config.daily_target_audio {
+            Some(path) => Some(PathBuf::from(path)),
+            None => {
+                resolve_resource_path(app_handle, format!("audio/{}", "alert-long-break.mp3")).ok()
+            }
+        },
         "audio-tick" => resolve_resource_path(app_handle, format!("audio/{}", "tick.mp3")).ok(),
         _ => None,
     }
@@ -0,0 +1,137 @@
+use crate::pomodoro::SessionType;
+use crate::screen_lock::SystemEvent;
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Something that happened to the timer, stored as one JSON object per line in the history file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub date: DateTime<Local>,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A session went to its end, `duration` is in seconds
+    SessionCompleted {
+        session_type: SessionType,
+        duration: u64,
+        label: Option<String>,
+    },
+    /// `seconds` were added to a session, `reopened` if it had already been completed
+    SessionExtended {
+        session_type: SessionType,
+        seconds: u64,
+        reopened: bool,
+    },
+    /// A running session was paused because of `cause`
+    SessionInterrupted {
+        session_type: SessionType,
+        cause: SystemEvent,
+    },
+    /// A running session was ended because of `cause`, see `lock_action`
+    SessionEnded {
+        session_type: SessionType,
+        cause: SystemEvent,
+    },
+    /// A session paused by a lock or a sleep was started again because of `cause`
+    SessionResumed {
+        session_type: SessionType,
+        cause: SystemEvent,
+    },
+}
+
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    pub fn new(path: &Path) -> Self {
+        History {
+            path: path.to_path_buf(),
+        }
+    }
+
+    pub fn record(&self, event: Event) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let entry = Entry {
+            date: Local::now(),
+            event,
+        };
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)
+    }
+
+    /// The entries of the history file, a missing file is an empty history.
+    pub fn entries(&self) -> io::Result<Vec<Entry>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    // Don't lose the whole history because of a bad line
+                    eprintln!("Ignoring invalid history entry `{line}`: {e}");
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Number of focus sessions completed on the local day `day`, a reopened session only counts
+    /// once.
+    pub fn completed_focus_count(&self, day: NaiveDate) -> io::Result<usize> {
+        Ok(completed_focus_count(&self.entries()?, day))
+    }
+}
+
+pub fn completed_focus_count(entries: &[Entry], day: NaiveDate) -> usize {
+    let of_the_day = entries
+        .iter()
+        .filter(|entry| entry.date.date_naive() == day);
+
+    let completed = of_the_day
+        .clone()
+        .filter(|entry| {
+            matches!(
+                entry.event,
+                Event::SessionCompleted {
+                    session_type: SessionType::Focus,
+                    ..
+                }
+            )
+        })
+        .count();
+    let reopened = of_the_day
+        .filter(|entry| {
+            matches!(
+                entry.event,
+                Event::SessionExtended {
+                    session_type: SessionType::Focus,
+                    reopened: true,
+                    ..
+                }
+            )
+        })
+        .count();
+
+    completed.saturating_sub(reopened)
+}
@@ -0,0 +1,252 @@
+// Commands of the user run when things happen to the sessions
+use crate::duration;
+use crate::pomodoro::{Pomodoro, Session, SessionStatus, SessionType};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// The commands run on each event, with the session as `POMODOROLM_*` environment variables and
+/// as JSON on stdin.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Hooks {
+    /// A focus session started
+    pub on_focus_start: Option<String>,
+    /// A focus session was completed
+    pub on_focus_end: Option<String>,
+    /// A break started
+    pub on_break_start: Option<String>,
+    /// A break was completed
+    pub on_break_end: Option<String>,
+    /// The running session was paused
+    pub on_pause: Option<String>,
+    /// The paused session was started again
+    pub on_resume: Option<String>,
+    /// A session was skipped
+    pub on_skip: Option<String>,
+    /// The last focus session of the cycle was completed and the long break follows
+    pub on_cycle_complete: Option<String>,
+    /// Time after which a hook still running is killed, like `30s`
+    #[serde(with = "duration::human")]
+    #[schemars(with = "duration::HumanDuration")]
+    pub timeout: Duration,
+}
+
+impl Default for Hooks {
+    fn default() -> Self {
+        Hooks {
+            on_focus_start: None,
+            on_focus_end: None,
+            on_break_start: None,
+            on_break_end: None,
+            on_pause: None,
+            on_resume: None,
+            on_skip: None,
+            on_cycle_complete: None,
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    FocusStart,
+    FocusEnd,
+    BreakStart,
+    BreakEnd,
+    Pause,
+    Resume,
+    Skip,
+    CycleComplete,
+}
+
+impl Hooks {
+    pub fn command(&self, event: HookEvent) -> Option<&str> {
+        match event {
+            HookEvent::FocusStart => &self.on_focus_start,
+            HookEvent::FocusEnd => &self.on_focus_end,
+            HookEvent::BreakStart => &self.on_break_start,
+            HookEvent::BreakEnd => &self.on_break_end,
+            HookEvent::Pause => &self.on_pause,
+            HookEvent::Resume => &self.on_resume,
+            HookEvent::Skip => &self.on_skip,
+            HookEvent::CycleComplete => &self.on_cycle_complete,
+        }
+        .as_deref()
+    }
+}
+
+/// What the hooks are told about the session the event happened to.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HookData {
+    pub event: HookEvent,
+    pub session_type: SessionType,
+    pub label: Option<String>,
+    /// Planned duration of the session, in seconds
+    pub duration: u64,
+    /// Time spent in the session, in seconds
+    pub elapsed: u64,
+    pub round: u16,
+}
+
+impl HookData {
+    fn new(event: HookEvent, pomodoro: &Pomodoro, session: &Session) -> Self {
+        HookData {
+            event,
+            session_type: session.session_type,
+            label: session.label.clone(),
+            duration: pomodoro.duration_of_session(session).as_secs(),
+            elapsed: session.current_time.as_secs(),
+            round: pomodoro.current_work_round_number,
+        }
+    }
+
+    /// The data as `POMODOROLM_*` environment variables.
+    pub fn env(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("POMODOROLM_EVENT", name(self.event)),
+            ("POMODOROLM_SESSION_TYPE", name(self.session_type)),
+            ("POMODOROLM_LABEL", self.label.clone().unwrap_or_default()),
+            ("POMODOROLM_DURATION", self.duration.to_string()),
+            ("POMODOROLM_ELAPSED", self.elapsed.to_string()),
+            ("POMODOROLM_ROUND", self.round.to_string()),
+        ]
+    }
+}
+
+// The name of a unit enum variant, as in the JSON
+fn name(value: impl Serialize) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+// Running and not waiting for its start delay anymore
+fn is_active(session: &Session) -> bool {
+    session.status == SessionStatus::Running && session.start_delay.is_zero()
+}
+
+fn start_event(session: &Session) -> HookEvent {
+    match session.session_type {
+        SessionType::Focus => HookEvent::FocusStart,
+        SessionType::ShortBreak | SessionType::LongBreak => HookEvent::BreakStart,
+    }
+}
+
+/// Why the pomodoro changed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Change {
+    /// The session went to its end, or the flow session was stopped
+    Completed,
+    Skipped,
+    /// A reset or an undo, which don't end the session
+    Reset,
+    /// A tick, or an action on the session like playing or pausing it
+    Other,
+}
+
+/// The events of going from `previous` to `current` because of `change`.
+pub fn events(previous: &Pomodoro, current: &Pomodoro, change: Change) -> Vec<HookData> {
+    let (before, after) = (&previous.current_session, &current.current_session);
+    let mut events = vec![];
+
+    let ended = match change {
+        Change::Skipped => Some(HookEvent::Skip),
+        Change::Completed if before.session_type == SessionType::Focus => Some(HookEvent::FocusEnd),
+        Change::Completed => Some(HookEvent::BreakEnd),
+        Change::Reset | Change::Other => None,
+    };
+
+    let Some(ended) = ended else {
+        let same_session = before.session_type == after.session_type
+            && before.step == after.step
+            && previous.current_work_round_number == current.current_work_round_number;
+
+        let event = match (
+            is_active(before),
+            before.status,
+            is_active(after),
+            after.status,
+        ) {
+            (true, _, _, SessionStatus::Paused) if same_session => Some(HookEvent::Pause),
+            (false, SessionStatus::Paused, true, _) if same_session => Some(HookEvent::Resume),
+            (false, _, true, _) => Some(start_event(after)),
+            _ => None,
+        };
+        events.extend(event.map(|event| HookData::new(event, current, after)));
+
+        return events;
+    };
+
+    events.push(HookData::new(ended, previous, before));
+
+    if change == Change::Completed
+        && before.session_type == SessionType::Focus
+        && after.session_type == SessionType::LongBreak
+    {
+        events.push(HookData::new(HookEvent::CycleComplete, previous, before));
+    }
+
+    if is_active(after) {
+        events.push(HookData::new(start_event(after), current, after));
+    }
+
+    events
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+/// Runs `command` with a shell, killing it after `timeout`, and logs what it prints.
+pub async fn run(command: &str, data: &HookData, timeout: Duration) -> Result<(), String> {
+    let json = serde_json::to_string(data).map_err(|e| e.to_string())?;
+
+    let mut child = shell(command)
+        .envs(data.env())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("unable to run `{command}`: {e}"))?;
+
+    let output = tokio::time::timeout(timeout, async move {
+        if let Some(mut stdin) = child.stdin.take() {
+            // The hook doesn't have to read its stdin
+            let _ = stdin.write_all(json.as_bytes()).await;
+        }
+        child.wait_with_output().await
+    })
+    .await
+    .map_err(|_| format!("`{command}` was killed after {}", duration::format(timeout)))?
+    .map_err(|e| format!("unable to run `{command}`: {e}"))?;
+
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        println!("[{command}] {line}");
+    }
+    for line in String::from_utf8_lossy(&output.stderr).lines() {
+        eprintln!("[{command}] {line}");
+    }
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("`{command}` failed with {}", output.status))
+    }
+}
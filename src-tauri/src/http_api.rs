@@ -0,0 +1,263 @@
+// Status and control of the running app over HTTP on localhost, for the Stream Deck setups,
+// the OBS overlays and the dashboards. Opt-in with `http_api_port`.
+//
+//   GET  /status                      the state sent to the webview, as JSON
+//   GET  /events                      the same state on every tick, as server-sent events
+//   POST /play, /pause, /skip, /reset the timer actions, answered with the new state
+use futures::StreamExt;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full, StreamBody};
+use hyper::body::{Bytes, Frame, Incoming};
+use hyper::header::{self, HeaderValue};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::convert::Infallible;
+use std::future::Future;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+type Body = BoxBody<Bytes, Infallible>;
+
+/// Listens on `port` of localhost only, the API isn't meant to be reachable from the network.
+pub async fn bind(port: u16) -> io::Result<TcpListener> {
+    TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, port))).await
+}
+
+/// Who can use the API.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Access {
+    /// Needed by the requests as an `Authorization: Bearer <token>` header or as a `token` query
+    /// parameter
+    pub token: String,
+    /// Origins of the web pages allowed to call the API, like `http://localhost:8080`
+    pub allowed_origins: Vec<String>,
+}
+
+/// Answers the requests made to `listener`, sending the timer actions to `handler` and the
+/// JSON of `states` to the clients. Only fails if the address of `listener` can't be read.
+pub async fn serve<F, Fut>(
+    listener: TcpListener,
+    access: Access,
+    handler: F,
+    states: watch::Receiver<String>,
+) -> io::Result<()>
+where
+    F: Fn(String) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+    let port = listener.local_addr()?.port();
+    let handler = Arc::new(handler);
+    let access = Arc::new(access);
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            // Like running out of file descriptors, which doesn't last
+            Err(e) => {
+                eprintln!("Unable to accept an HTTP API connection: {e}.");
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let (handler, access, states) = (handler.clone(), access.clone(), states.clone());
+
+        tokio::spawn(async move {
+            let service = service_fn(move |request| {
+                let (handler, access, states) = (handler.clone(), access.clone(), states.clone());
+                async move {
+                    Ok::<_, Infallible>(
+                        respond(request, port, &access, handler.as_ref(), states).await,
+                    )
+                }
+            });
+
+            let _ = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await;
+        });
+    }
+}
+
+async fn respond<F, Fut>(
+    request: Request<Incoming>,
+    port: u16,
+    access: &Access,
+    handler: &F,
+    states: watch::Receiver<String>,
+) -> Response<Body>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let origin = request
+        .headers()
+        .get(header::ORIGIN)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let mut response = answer(request, port, access, handler, states).await;
+
+    // Only the pages of the allowed origins can read the answers in the browsers
+    if let Some(origin) = origin
+        && access.allowed_origins.contains(&origin)
+        && let Ok(origin) = HeaderValue::from_str(&origin)
+    {
+        let headers = response.headers_mut();
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            HeaderValue::from_static("GET, POST"),
+        );
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_HEADERS,
+            HeaderValue::from_static("authorization"),
+        );
+    }
+    response
+        .headers_mut()
+        .insert(header::VARY, HeaderValue::from_static("origin"));
+
+    response
+}
+
+async fn answer<F, Fut>(
+    request: Request<Incoming>,
+    port: u16,
+    access: &Access,
+    handler: &F,
+    states: watch::Receiver<String>,
+) -> Response<Body>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    // Against the DNS rebinding, which would make the pages of any site look local
+    if !is_local_host(&request, port) {
+        return text(StatusCode::FORBIDDEN, "wrong host");
+    }
+
+    if request.method() == Method::OPTIONS {
+        return text(StatusCode::NO_CONTENT, "");
+    }
+
+    if !is_authorized(&request, &access.token) {
+        return text(StatusCode::UNAUTHORIZED, "missing or wrong token");
+    }
+
+    let message = match request.uri().path() {
+        "/play" => Some("play"),
+        "/pause" => Some("pause"),
+        "/skip" => Some("skip"),
+        "/reset" => Some("reset_round"),
+        _ => None,
+    };
+
+    match (request.method(), request.uri().path(), message) {
+        (&Method::GET, "/status", _) => json(states.borrow().clone()),
+        (&Method::GET, "/events", _) => events(states),
+        (&Method::POST, _, Some(message)) => match handler(message.to_string()).await {
+            Ok(()) => json(states.borrow().clone()),
+            Err(e) => text(StatusCode::CONFLICT, &e),
+        },
+        (_, "/status" | "/events", _) | (_, _, Some(_)) => {
+            text(StatusCode::METHOD_NOT_ALLOWED, "method not allowed")
+        }
+        _ => text(StatusCode::NOT_FOUND, "not found"),
+    }
+}
+
+fn is_local_host(request: &Request<Incoming>, port: u16) -> bool {
+    let Some(host) = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+
+    ["localhost", "127.0.0.1"]
+        .iter()
+        .any(|name| host == format!("{name}:{port}") || (port == 80 && host == *name))
+}
+
+fn is_authorized(request: &Request<Incoming>, token: &str) -> bool {
+    // An empty token would let everyone in
+    if token.is_empty() {
+        return false;
+    }
+
+    let header = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    // The browsers can't add headers to the server-sent events
+    let query = request.uri().query().and_then(|query| {
+        query
+            .split('&')
+            .find_map(|parameter| parameter.strip_prefix("token="))
+    });
+
+    [header, query]
+        .into_iter()
+        .flatten()
+        .any(|given| constant_time_eq(given.as_bytes(), token.as_bytes()))
+}
+
+// Takes as long whatever the number of matching bytes, not to tell the token byte by byte
+fn constant_time_eq(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len()
+        && given
+            .iter()
+            .zip(expected)
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+// The current state, then a new one every time it is sent
+fn events(mut states: watch::Receiver<String>) -> Response<Body> {
+    let current = states.borrow_and_update().clone();
+    let next = futures::stream::unfold(states, |mut states| async move {
+        states.changed().await.ok()?;
+        let state = states.borrow_and_update().clone();
+        Some((state, states))
+    });
+    let frames = futures::stream::once(async { current })
+        .chain(next)
+        .map(|state| Ok(Frame::data(Bytes::from(format!("data: {state}\n\n")))));
+
+    response(
+        StatusCode::OK,
+        "text/event-stream",
+        BodyExt::boxed(StreamBody::new(frames)),
+    )
+}
+
+fn json(state: String) -> Response<Body> {
+    response(
+        StatusCode::OK,
+        "application/json",
+        Full::from(state).boxed(),
+    )
+}
+
+fn text(status: StatusCode, message: &str) -> Response<Body> {
+    response(
+        status,
+        "text/plain; charset=utf-8",
+        Full::from(message.to_string()).boxed(),
+    )
+}
+
+fn response(status: StatusCode, content_type: &'static str, body: Body) -> Response<Body> {
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response
+}
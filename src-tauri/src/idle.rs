@@ -0,0 +1,178 @@
+// Pauses the focus sessions while the user is away from the computer
+use crate::pomodoro::{Pomodoro, SessionStatus, SessionType};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+
+/// How often the idle time is checked.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What to do with the idle time of a session paused because the user was away.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IdleTimePolicy {
+    /// Ask the user when they come back
+    #[default]
+    Ask,
+    /// Count it as focus time
+    Keep,
+    /// Take it off the session
+    Discard,
+}
+
+/// Something that knows since when the user hasn't touched the computer.
+pub trait IdleMonitor {
+    /// Time since the last input of the user, `None` if it can't be known.
+    fn idle_time(&self) -> impl Future<Output = Option<Duration>> + Send;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum IdleEvent {
+    /// The user has been away during the running focus session for this long, it should be
+    /// paused
+    Away(Duration),
+    /// The user is back after the session was paused, with the idle time it counted
+    Back(Duration),
+}
+
+/// Follows the idle time of the user during the focus sessions.
+#[derive(Debug, Default)]
+pub struct IdleWatcher {
+    // Idle time counted by the paused session, and the last idle time seen since
+    away: Option<(Duration, Duration)>,
+}
+
+impl IdleWatcher {
+    /// Checks the idle time given by `monitor`, see `update`.
+    pub async fn check(
+        &mut self,
+        monitor: &impl IdleMonitor,
+        pause_after: Duration,
+        pomodoro: &Pomodoro,
+    ) -> Option<IdleEvent> {
+        let idle_time = monitor.idle_time().await?;
+        self.update(pause_after, idle_time, pomodoro)
+    }
+
+    /// The user went away from the running focus session of `pomodoro` if they have been idle
+    /// for `pause_after`, and is back once the idle time goes down.
+    pub fn update(
+        &mut self,
+        pause_after: Duration,
+        idle_time: Duration,
+        pomodoro: &Pomodoro,
+    ) -> Option<IdleEvent> {
+        let session = &pomodoro.current_session;
+
+        match self.away {
+            None if session.session_type == SessionType::Focus
+                && session.status == SessionStatus::Running
+                && session.start_delay.is_zero()
+                && idle_time >= pause_after =>
+            {
+                // The session may have started after the user went away
+                let counted = idle_time.min(session.current_time);
+                self.away = Some((counted, idle_time));
+                Some(IdleEvent::Away(counted))
+            }
+            // The session was started again some other way
+            Some(_) if session.status != SessionStatus::Paused => {
+                self.away = None;
+                None
+            }
+            Some((counted, last_idle_time)) if idle_time < last_idle_time => {
+                self.away = None;
+                Some(IdleEvent::Back(counted))
+            }
+            Some((counted, _)) => {
+                self.away = Some((counted, idle_time));
+                None
+            }
+            None => None,
+        }
+    }
+}
+
+/// The idle time of the desktop, through the Mutter or the freedesktop screen saver D-Bus
+/// interfaces on Linux.
+pub struct DesktopIdleMonitor {
+    // The same connection for all the polls
+    #[cfg(target_os = "linux")]
+    connection: Option<zbus::Connection>,
+}
+
+impl DesktopIdleMonitor {
+    #[cfg(target_os = "linux")]
+    pub async fn new() -> Self {
+        let connection = match zbus::Connection::session().await {
+            Ok(connection) => Some(connection),
+            Err(e) => {
+                eprintln!("Unable to connect to the session bus to follow the idle time: {e:?}.");
+                None
+            }
+        };
+
+        DesktopIdleMonitor { connection }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub async fn new() -> Self {
+        DesktopIdleMonitor {}
+    }
+}
+
+impl IdleMonitor for DesktopIdleMonitor {
+    #[cfg(target_os = "linux")]
+    async fn idle_time(&self) -> Option<Duration> {
+        let connection = self.connection.as_ref()?;
+
+        match dbus::mutter_idle_time(connection).await {
+            Ok(idle_time) => Some(idle_time),
+            Err(_) => dbus::screen_saver_idle_time(connection).await.ok(),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn idle_time(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod dbus {
+    use std::time::Duration;
+    use zbus::Connection;
+
+    // GNOME
+    pub async fn mutter_idle_time(connection: &Connection) -> zbus::Result<Duration> {
+        let reply = connection
+            .call_method(
+                Some("org.gnome.Mutter.IdleMonitor"),
+                "/org/gnome/Mutter/IdleMonitor/Core",
+                Some("org.gnome.Mutter.IdleMonitor"),
+                "GetIdletime",
+                &(),
+            )
+            .await?;
+
+        let milliseconds: u64 = reply.body().deserialize()?;
+        Ok(Duration::from_millis(milliseconds))
+    }
+
+    // KDE and the other desktops implementing the freedesktop screen saver interface
+    pub async fn screen_saver_idle_time(connection: &Connection) -> zbus::Result<Duration> {
+        let reply = connection
+            .call_method(
+                Some("org.freedesktop.ScreenSaver"),
+                "/org/freedesktop/ScreenSaver",
+                Some("org.freedesktop.ScreenSaver"),
+                "GetSessionIdleTime",
+                &(),
+            )
+            .await?;
+
+        let seconds: u32 = reply.body().deserialize()?;
+        Ok(Duration::from_secs(seconds.into()))
+    }
+}
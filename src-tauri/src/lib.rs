@@ -1,13 +1,28 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 pub mod cli;
-mod config;
+pub mod color;
+pub mod color_scheme;
+pub mod config;
+pub mod control;
+#[cfg(target_os = "linux")]
+pub mod dbus_service;
+pub mod do_not_disturb;
+pub mod duration;
 pub mod gui;
+pub mod history;
+pub mod hooks;
+pub mod http_api;
 mod icon;
+pub mod idle;
+pub mod notification;
 pub mod pomodoro;
+pub mod screen_lock;
 mod sound;
+pub mod theme;
+pub mod theme_import;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run_gui(config_dir_name: &str) {
-    gui::run_app(config_dir_name, tauri::Builder::default())
+pub fn run_gui(config_source: config::ConfigSource) {
+    gui::run_app(config_source, tauri::Builder::default())
 }
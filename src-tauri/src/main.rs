@@ -2,6 +2,10 @@
 use clap::Parser;
 
 use clap::Subcommand;
+use pomodorolm_lib::cli::SchemaKind;
+use pomodorolm_lib::config::{ConfigSource, Overrides};
+use pomodorolm_lib::theme_import::ThemeFormat;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -9,12 +13,102 @@ use clap::Subcommand;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    /// Config file to use instead of `config.toml` in the config directory
+    #[arg(long, global = true, env = "POMODOROLM_CONFIG", value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Directory of the config file and of the custom themes
+    #[arg(long, global = true, env = "POMODOROLM_CONFIG_DIR", value_name = "DIR")]
+    config_dir: Option<PathBuf>,
+    /// Override a setting of the config file, like `--set focus_duration=1500`.
+    /// Takes precedence over the `POMODOROLM_<SETTING>` environment variables.
+    #[arg(long = "set", global = true, value_name = "SETTING=VALUE")]
+    settings: Vec<String>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Run the CLI version of the app
     Cli,
+    /// Read and change the settings
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Manage the config profiles
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+    /// Manage themes
+    Theme {
+        #[command(subcommand)]
+        command: ThemeCommands,
+    },
+    /// Add time to the current session of the running app, like `5m`, up to
+    /// `max_session_duration`
+    Extend { duration: String },
+    /// Go back to the session of the running app before the last skip or reset
+    Undo,
+    /// Stop the flow session of the running app and go on to the suggested break
+    Stop,
+    /// Print the JSON Schema of the config file or of the theme files
+    Schema {
+        #[arg(value_enum)]
+        kind: SchemaKind,
+    },
+}
+
+#[derive(Subcommand)]
+enum ThemeCommands {
+    /// Convert a color scheme to a Pomodorolm theme and add it to the user themes
+    Import {
+        /// Color scheme file to import
+        path: PathBuf,
+        /// Format of the file, guessed from its extension if not provided
+        #[arg(long, value_enum)]
+        format: Option<ThemeFormat>,
+        /// Name of the theme, taken from the file if not provided
+        #[arg(long)]
+        name: Option<String>,
+        /// Overwrite the theme if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print the value of a setting
+    Get { setting: String },
+    /// Change a setting, durations can be given like `25m` or `1h30m`
+    Set { setting: String, value: String },
+    /// List all the settings
+    List,
+    /// Go back to the default value of a setting, or of all of them except the tables and the
+    /// lists, like the profiles or the steps of the sequence
+    Reset { setting: Option<String> },
+    /// Open the config file in $EDITOR, it's only saved if it's valid
+    Edit,
+    /// Print the config file
+    Show {
+        /// Print the config the app runs with, once the overrides and the active profile are
+        /// applied
+        #[arg(long)]
+        effective: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommands {
+    /// List the profiles defined in the config file
+    List,
+    /// Switch to a profile, the running app picks up the change
+    Use {
+        /// Name of the profile, as in `[profiles.<name>]`
+        name: String,
+    },
+    /// Go back to the settings outside of any profile
+    Clear,
 }
 
 const CONFIG_DIR_NAME: &str = "pomodorolm";
@@ -22,12 +116,125 @@ const CONFIG_DIR_NAME: &str = "pomodorolm";
 fn main() {
     let cli = Cli::parse();
 
+    let config_source = match Overrides::parse(&cli.settings).and_then(|settings| {
+        ConfigSource::new(
+            CONFIG_DIR_NAME,
+            cli.config_dir.clone(),
+            cli.config.clone(),
+            Overrides::from_env().and(settings),
+        )
+    }) {
+        Ok(config_source) => config_source,
+        Err(e) => {
+            eprintln!("Invalid config options: {e}");
+            std::process::exit(1);
+        }
+    };
+
     // You can check for the existence of subcommands, and if found use their
     // matches just as you would the top level cmd
     match &cli.command {
         Some(command) => match command {
-            Commands::Cli => pomodorolm_lib::cli::run(CONFIG_DIR_NAME),
+            Commands::Cli => pomodorolm_lib::cli::run(&config_source),
+            Commands::Config { command } => {
+                let result = match command {
+                    ConfigCommands::Get { setting } => {
+                        pomodorolm_lib::cli::get_setting(&config_source, setting)
+                            .map(|value| println!("{value}"))
+                    }
+                    ConfigCommands::Set { setting, value } => {
+                        pomodorolm_lib::cli::set_setting(&config_source, setting, value)
+                    }
+                    ConfigCommands::List => {
+                        pomodorolm_lib::cli::list_settings(&config_source).map(|settings| {
+                            for (setting, value) in settings {
+                                println!("{setting} = {value}");
+                            }
+                        })
+                    }
+                    ConfigCommands::Reset { setting } => {
+                        pomodorolm_lib::cli::reset_settings(&config_source, setting.as_deref())
+                    }
+                    ConfigCommands::Edit => pomodorolm_lib::cli::edit_config(&config_source),
+                    ConfigCommands::Show { effective } => {
+                        pomodorolm_lib::cli::show_config(&config_source, *effective)
+                            .map(|config| print!("{config}"))
+                    }
+                };
+
+                if let Err(e) = result {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Profile { command } => {
+                let result = match command {
+                    ProfileCommands::List => pomodorolm_lib::cli::list_profiles(&config_source)
+                        .map(|profiles| {
+                            for (name, active) in profiles {
+                                println!("{} {name}", if active { "*" } else { " " });
+                            }
+                        }),
+                    ProfileCommands::Use { name } => {
+                        pomodorolm_lib::cli::use_profile(&config_source, Some(name.clone()))
+                    }
+                    ProfileCommands::Clear => {
+                        pomodorolm_lib::cli::use_profile(&config_source, None)
+                    }
+                };
+
+                if let Err(e) = result {
+                    eprintln!("Unable to update the profile: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Theme {
+                command:
+                    ThemeCommands::Import {
+                        path,
+                        format,
+                        name,
+                        force,
+                    },
+            } => match pomodorolm_lib::cli::import_theme(
+                &config_source,
+                path,
+                *format,
+                name.clone(),
+                *force,
+            ) {
+                Ok(theme_path) => println!("Theme imported to {}", theme_path.display()),
+                Err(e) => {
+                    eprintln!("Unable to import theme: {e}");
+                    std::process::exit(1);
+                }
+            },
+            Commands::Extend { duration } => {
+                if let Err(e) = pomodorolm_lib::cli::extend_session(&config_source, duration) {
+                    eprintln!("Unable to extend the session: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Undo => {
+                if let Err(e) = pomodorolm_lib::cli::undo(&config_source) {
+                    eprintln!("Unable to undo: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Stop => {
+                if let Err(e) = pomodorolm_lib::cli::stop(&config_source) {
+                    eprintln!("Unable to stop the session: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Schema { kind } => match pomodorolm_lib::cli::schema(*kind) {
+                Ok(schema) => println!("{schema}"),
+                Err(e) => {
+                    eprintln!("Unable to generate the schema: {e}");
+                    std::process::exit(1);
+                }
+            },
         },
-        None => pomodorolm_lib::run_gui(CONFIG_DIR_NAME),
+        None => pomodorolm_lib::run_gui(config_source),
     }
 }
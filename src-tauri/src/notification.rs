@@ -0,0 +1,388 @@
+use crate::pomodoro::SessionType;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Title and body of a notification, both can use the placeholders `{label}`, `{next_session}`,
+/// `{duration}`, `{round}` and `{today_count}`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct NotificationTemplate {
+    pub title: String,
+    pub body: String,
+}
+
+impl NotificationTemplate {
+    fn new(title: &str, body: &str) -> Self {
+        NotificationTemplate {
+            title: title.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    pub fn render(&self, context: &NotificationContext) -> (String, String) {
+        (render(&self.title, context), render(&self.body, context))
+    }
+}
+
+/// The notifications sent at the end of each kind of session.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct NotificationTemplates {
+    /// A focus round ended and a short break follows
+    pub focus_end: NotificationTemplate,
+    /// A short break ended
+    pub short_break_end: NotificationTemplate,
+    /// A long break ended
+    pub long_break_end: NotificationTemplate,
+    /// The last focus round of the cycle ended and the long break follows
+    pub cycle_complete: NotificationTemplate,
+    /// The focus round that ended reached `daily_focus_target`
+    pub daily_target_reached: NotificationTemplate,
+}
+
+impl Default for NotificationTemplates {
+    fn default() -> Self {
+        NotificationTemplates {
+            focus_end: NotificationTemplate::new(
+                "Focus round completed",
+                "Start a {duration} {next_session}",
+            ),
+            short_break_end: NotificationTemplate::new(
+                "Short break completed",
+                "Start a {duration} {next_session}",
+            ),
+            long_break_end: NotificationTemplate::new(
+                "Long break completed",
+                "Start a {duration} {next_session}",
+            ),
+            cycle_complete: NotificationTemplate::new(
+                "Cycle completed",
+                "{round} focus rounds done, start a {duration} {next_session}",
+            ),
+            daily_target_reached: NotificationTemplate::new(
+                "Done for the day",
+                "{today_count} focus rounds completed today",
+            ),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NotificationEvent {
+    FocusEnd,
+    ShortBreakEnd,
+    LongBreakEnd,
+    CycleComplete,
+    DailyTargetReached,
+}
+
+impl NotificationEvent {
+    /// The event of going from a `previous` session to a `next` one, which can be of the same
+    /// type with the sequences.
+    pub fn between(previous: SessionType, next: SessionType) -> Self {
+        match (previous, next) {
+            (SessionType::Focus, SessionType::LongBreak) => NotificationEvent::CycleComplete,
+            (SessionType::Focus, _) => NotificationEvent::FocusEnd,
+            (SessionType::ShortBreak, _) => NotificationEvent::ShortBreakEnd,
+            (SessionType::LongBreak, _) => NotificationEvent::LongBreakEnd,
+        }
+    }
+}
+
+impl NotificationTemplates {
+    pub fn template(&self, event: NotificationEvent) -> &NotificationTemplate {
+        match event {
+            NotificationEvent::FocusEnd => &self.focus_end,
+            NotificationEvent::ShortBreakEnd => &self.short_break_end,
+            NotificationEvent::LongBreakEnd => &self.long_break_end,
+            NotificationEvent::CycleComplete => &self.cycle_complete,
+            NotificationEvent::DailyTargetReached => &self.daily_target_reached,
+        }
+    }
+}
+
+/// The values of the placeholders.
+pub struct NotificationContext {
+    /// Label of the session that ended
+    pub label: String,
+    pub next_session: SessionType,
+    /// Duration of the next session
+    pub duration: Duration,
+    /// Round of the session that ended
+    pub round: u16,
+    /// Focus sessions completed today
+    pub today_count: usize,
+}
+
+fn session_name(session_type: SessionType) -> &'static str {
+    match session_type {
+        SessionType::Focus => "focus round",
+        SessionType::ShortBreak => "short break",
+        SessionType::LongBreak => "long break",
+    }
+}
+
+// "1 minute", "25 minutes", and seconds for the sessions shorter than a minute
+fn format_duration(duration: Duration) -> String {
+    let (value, unit) = if duration.as_secs() < 60 {
+        (duration.as_secs(), "second")
+    } else {
+        (minutes(duration), "minute")
+    };
+
+    format!("{value} {unit}{}", if value > 1 { "s" } else { "" })
+}
+
+/// Replaces the placeholders of `template`, unknown ones are kept as they are.
+pub fn render(template: &str, context: &NotificationContext) -> String {
+    template
+        .replace("{label}", &context.label)
+        .replace("{next_session}", session_name(context.next_session))
+        .replace("{duration}", &format_duration(context.duration))
+        .replace("{round}", &context.round.to_string())
+        .replace("{today_count}", &context.today_count.to_string())
+}
+
+/// What can be done from a notification, each action is a message of `handle_external_message`
+/// except `Snooze` which holds the next session for `snooze_duration` and `Extend` which reopens
+/// the session that ended for `extend_duration`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NotificationAction {
+    Extend,
+    Play,
+    Skip,
+    Snooze,
+}
+
+impl NotificationAction {
+    pub fn key(self) -> &'static str {
+        match self {
+            NotificationAction::Extend => "extend",
+            NotificationAction::Play => "play",
+            NotificationAction::Skip => "skip",
+            NotificationAction::Snooze => "snooze",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        [
+            NotificationAction::Extend,
+            NotificationAction::Play,
+            NotificationAction::Skip,
+            NotificationAction::Snooze,
+        ]
+        .into_iter()
+        .find(|action| action.key() == key)
+    }
+}
+
+/// The actions offered when `next_session` starts, with their labels.
+/// There is nothing to start if the next session already runs, and the session that ended can
+/// only be extended if `extend_duration` is set.
+pub fn actions(
+    next_session: SessionType,
+    next_session_running: bool,
+    snooze_duration: Duration,
+    extend_duration: Option<Duration>,
+) -> Vec<(NotificationAction, String)> {
+    let mut actions = vec![];
+
+    if !next_session_running {
+        actions.push((
+            NotificationAction::Play,
+            match next_session {
+                SessionType::Focus => "Start focus",
+                SessionType::ShortBreak | SessionType::LongBreak => "Start break",
+            }
+            .to_string(),
+        ));
+    }
+
+    if let Some(extend_duration) = extend_duration {
+        actions.push((
+            NotificationAction::Extend,
+            format!("+{} min", minutes(extend_duration)),
+        ));
+    }
+
+    actions.push((
+        NotificationAction::Snooze,
+        format!("Snooze {} min", minutes(snooze_duration)),
+    ));
+
+    if next_session != SessionType::Focus {
+        actions.push((NotificationAction::Skip, "Skip break".to_string()));
+    }
+
+    actions
+}
+
+// Rounded to the nearest minute, at least 1
+fn minutes(duration: Duration) -> u64 {
+    ((duration.as_secs_f64() / 60.0).round() as u64).max(1)
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    /// Usually kept on screen until dismissed
+    Critical,
+}
+
+pub struct DesktopNotification {
+    pub title: String,
+    pub body: String,
+    /// Path of the icon
+    pub icon: String,
+    pub actions: Vec<(NotificationAction, String)>,
+    pub urgency: Urgency,
+    /// Time before the notification expires, `Some(Duration::ZERO)` to keep it until dismissed
+    /// and `None` to let the notification daemon decide
+    pub timeout: Option<Duration>,
+}
+
+/// The remaining time of a session, like `4:05`.
+pub fn countdown(remaining: Duration) -> String {
+    let seconds = remaining.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Notifications with actions through the `org.freedesktop.Notifications` D-Bus service.
+#[cfg(target_os = "linux")]
+pub mod freedesktop {
+    use super::{DesktopNotification, NotificationAction, Urgency};
+    use futures::StreamExt;
+    use std::collections::HashMap;
+    use zbus::zvariant::Value;
+    use zbus::{Connection, MatchRule, MessageStream};
+
+    pub const DESTINATION: &str = "org.freedesktop.Notifications";
+    pub const PATH: &str = "/org/freedesktop/Notifications";
+    pub const INTERFACE: &str = "org.freedesktop.Notifications";
+
+    const APP_NAME: &str = "Pomodorolm";
+
+    /// A notification shown by the notification daemon.
+    pub struct SentNotification {
+        pub id: u32,
+        signals: MessageStream,
+    }
+
+    /// Shows `notification`, in place of the notification `replaces_id` if it isn't 0, and
+    /// listens to its actions.
+    pub async fn send(
+        connection: &Connection,
+        notification: &DesktopNotification,
+        replaces_id: u32,
+    ) -> zbus::Result<SentNotification> {
+        // Listen before sending the notification to not miss a quick answer
+        let signals = MessageStream::for_match_rule(
+            MatchRule::builder()
+                .msg_type(zbus::message::Type::Signal)
+                .interface(INTERFACE)?
+                .path(PATH)?
+                .build(),
+            connection,
+            None,
+        )
+        .await?;
+
+        Ok(SentNotification {
+            id: notify(connection, notification, replaces_id).await?,
+            signals,
+        })
+    }
+
+    /// Shows `notification` without listening to its actions, returns its id.
+    pub async fn notify(
+        connection: &Connection,
+        notification: &DesktopNotification,
+        replaces_id: u32,
+    ) -> zbus::Result<u32> {
+        // Actions are a flat list of key and label pairs
+        let actions: Vec<&str> = notification
+            .actions
+            .iter()
+            .flat_map(|(action, label)| [action.key(), label.as_str()])
+            .collect();
+        let hints: HashMap<&str, Value> = HashMap::from([(
+            "urgency",
+            Value::U8(match notification.urgency {
+                Urgency::Low => 0,
+                Urgency::Normal => 1,
+                Urgency::Critical => 2,
+            }),
+        )]);
+        // In milliseconds, -1 lets the daemon decide and 0 never expires
+        let expire_timeout = notification.timeout.map_or(-1, |timeout| {
+            i32::try_from(timeout.as_millis()).unwrap_or(i32::MAX)
+        });
+
+        let reply = connection
+            .call_method(
+                Some(DESTINATION),
+                PATH,
+                Some(INTERFACE),
+                "Notify",
+                &(
+                    APP_NAME,
+                    replaces_id,
+                    notification.icon.as_str(),
+                    notification.title.as_str(),
+                    notification.body.as_str(),
+                    actions,
+                    hints,
+                    expire_timeout,
+                ),
+            )
+            .await?;
+
+        reply.body().deserialize()
+    }
+
+    pub async fn close(connection: &Connection, id: u32) -> zbus::Result<()> {
+        connection
+            .call_method(
+                Some(DESTINATION),
+                PATH,
+                Some(INTERFACE),
+                "CloseNotification",
+                &(id,),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    impl SentNotification {
+        /// Waits until one of the actions is chosen, `None` if the notification is closed
+        /// without choosing any.
+        pub async fn action(mut self) -> zbus::Result<Option<NotificationAction>> {
+            while let Some(message) = self.signals.next().await {
+                let message = message?;
+                let header = message.header();
+
+                match header.member().map(|member| member.as_str()) {
+                    Some("ActionInvoked") => {
+                        let (id, key): (u32, String) = message.body().deserialize()?;
+                        if id == self.id {
+                            return Ok(NotificationAction::from_key(&key));
+                        }
+                    }
+                    Some("NotificationClosed") => {
+                        let (id, _reason): (u32, u32) = message.body().deserialize()?;
+                        if id == self.id {
+                            return Ok(None);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            Ok(None)
+        }
+    }
+}
@@ -1,4 +1,7 @@
+use crate::duration;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(PartialEq, Copy, Debug, Serialize, Deserialize, Clone)]
 pub enum SessionStatus {
@@ -7,42 +10,87 @@ pub enum SessionStatus {
     Running,
 }
 
-#[derive(Copy, Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[derive(Copy, Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema)]
 pub enum SessionType {
     Focus,
     ShortBreak,
     LongBreak,
 }
 
-type Seconds = u16;
+/// Time between two calls to `tick`.
+pub const TICK: Duration = Duration::from_secs(1);
 
-#[derive(PartialEq, Copy, Debug, Serialize, Deserialize, Clone)]
+// Whole seconds, for the front-end
+type Seconds = u64;
+
+/// A session of a custom sequence.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct Step {
+    pub name: String,
+    pub session_type: SessionType,
+    #[serde(with = "duration::seconds")]
+    pub duration: Duration,
+    // Start the step without waiting for the user
+    pub auto_start: bool,
+}
+
+// Its durations are numbers of seconds in the JSON sent to the front-end and the HTTP API, like
+// in `config::Config::to_front_end`
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
+    // Grace period before a session that starts by itself actually starts
+    #[serde(with = "duration::seconds")]
+    pub auto_start_delay: Duration,
     pub auto_start_long_break_timer: bool,
     pub auto_start_short_break_timer: bool,
     pub auto_start_focus_timer: bool,
-    pub focus_duration: Seconds,
-    pub long_break_duration: Seconds,
+    // Focus rounds completed in a day after which nothing starts by itself anymore, whatever
+    // the cycle they are in
+    pub daily_focus_target: Option<u16>,
+    // Part of the time of a flow session that the break after it lasts
+    pub flow_break_ratio: f32,
+    // Focus sessions count up until they are stopped, or until `max_session_duration`
+    pub flow_mode: bool,
+    #[serde(with = "duration::seconds")]
+    pub focus_duration: Duration,
+    #[serde(with = "duration::seconds")]
+    pub long_break_duration: Duration,
+    // Focus rounds before a long break
     pub max_focus_rounds: u16,
-    pub short_break_duration: Seconds,
+    // No session can be extended past it
+    #[serde(with = "duration::seconds")]
+    pub max_session_duration: Duration,
+    // Steps to go through in a loop instead of the classic cycle of focus rounds and breaks, if
+    // not empty
+    pub sequence: Vec<Step>,
+    #[serde(with = "duration::seconds")]
+    pub short_break_duration: Duration,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
+            auto_start_delay: Duration::ZERO,
             auto_start_long_break_timer: false,
             auto_start_short_break_timer: false,
             auto_start_focus_timer: false,
-            focus_duration: 25 * 60,
-            long_break_duration: 20 * 60,
+            daily_focus_target: None,
+            flow_break_ratio: 0.2,
+            flow_mode: false,
+            focus_duration: Duration::from_secs(25 * 60),
+            long_break_duration: Duration::from_secs(20 * 60),
             max_focus_rounds: 4,
-            short_break_duration: 5 * 60,
+            max_session_duration: Duration::from_secs(90 * 60),
+            sequence: vec![],
+            short_break_duration: Duration::from_secs(5 * 60),
         }
     }
 }
 
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub struct Pomodoro {
+    // Focus sessions that went to their end today, see `new_day`
+    pub completed_focus_rounds: u16,
     pub config: Config,
     pub current_session: Session,
     // A work round is a Focus + a Break (short or long)
@@ -51,30 +99,46 @@ pub struct Pomodoro {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PomodoroUnborrowed {
+    pub completed_focus_rounds: u16,
     pub config: Config,
     pub current_session: SessionUnburrowed,
     pub current_work_round_number: u16,
+    pub done_for_the_day: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SessionUnburrowed {
     current_time: Seconds,
+    // Including the extra time
+    duration: Seconds,
+    extra_time: Seconds,
+    // Counts up instead of down
+    flow: bool,
     label: Option<String>,
     session_type: SessionType,
+    start_delay: Seconds,
     status: SessionStatus,
+    step: usize,
 }
 
 impl Default for Pomodoro {
     fn default() -> Self {
         Pomodoro {
+            completed_focus_rounds: 0,
             config: Config {
+                auto_start_delay: Duration::ZERO,
                 auto_start_long_break_timer: false,
                 auto_start_short_break_timer: false,
                 auto_start_focus_timer: false,
-                focus_duration: 25 * 60,
-                long_break_duration: 20 * 60,
+                daily_focus_target: None,
+                flow_break_ratio: 0.2,
+                flow_mode: false,
+                focus_duration: Duration::from_secs(25 * 60),
+                long_break_duration: Duration::from_secs(20 * 60),
                 max_focus_rounds: 4,
-                short_break_duration: 5 * 60,
+                max_session_duration: Duration::from_secs(90 * 60),
+                sequence: vec![],
+                short_break_duration: Duration::from_secs(5 * 60),
             },
             current_session: Session::default(),
             current_work_round_number: 1,
@@ -82,47 +146,136 @@ impl Default for Pomodoro {
     }
 }
 impl Pomodoro {
-    pub fn duration_of_session(&self, session: &Session) -> Seconds {
-        match session.session_type {
-            SessionType::Focus => self.config.focus_duration,
-            SessionType::LongBreak => self.config.long_break_duration,
-            SessionType::ShortBreak => self.config.short_break_duration,
+    /// A pomodoro at the start of the sequence of `config`.
+    pub fn new(config: Config) -> Self {
+        Pomodoro {
+            current_session: first_session(&config),
+            config,
+            ..Pomodoro::default()
         }
     }
 
+    pub fn duration_of_session(&self, session: &Session) -> Duration {
+        let duration = match (
+            session.duration,
+            self.step_of(session),
+            session.session_type,
+        ) {
+            (Some(duration), _, _) => duration,
+            // Flow sessions only end by themselves once they can't be any longer
+            _ if self.is_flow_session(session) => self.config.max_session_duration,
+            (None, Some(step), _) => step.duration,
+            (None, None, SessionType::Focus) => self.config.focus_duration,
+            (None, None, SessionType::LongBreak) => self.config.long_break_duration,
+            (None, None, SessionType::ShortBreak) => self.config.short_break_duration,
+        };
+
+        duration.saturating_add(session.extra_time)
+    }
+
+    /// The step of the sequence `session` is at, `None` for the classic cycle.
+    pub fn step_of(&self, session: &Session) -> Option<&Step> {
+        self.config.sequence.get(session.step)
+    }
+
+    /// `session` is a focus session of the flow mode, which has no fixed duration.
+    pub fn is_flow_session(&self, session: &Session) -> bool {
+        self.config.flow_mode && session.session_type == SessionType::Focus
+    }
+
+    /// The break suggested after the current flow session, `flow_break_ratio` of its time.
+    pub fn flow_break(&self) -> Duration {
+        self.current_session
+            .current_time
+            .mul_f32(self.config.flow_break_ratio)
+            .max(TICK)
+    }
+
+    /// The daily focus target is reached, if there is one.
+    pub fn is_done_for_the_day(&self) -> bool {
+        self.config
+            .daily_focus_target
+            .is_some_and(|target| self.completed_focus_rounds >= target)
+    }
+
     pub fn to_unborrowed(&self) -> PomodoroUnborrowed {
         PomodoroUnborrowed {
-            config: self.config,
+            completed_focus_rounds: self.completed_focus_rounds,
+            config: self.config.clone(),
             current_work_round_number: self.current_work_round_number,
             current_session: SessionUnburrowed {
-                current_time: self.current_session.current_time,
+                current_time: self.current_session.current_time.as_secs(),
+                duration: self.duration_of_session(&self.current_session).as_secs(),
+                extra_time: self.current_session.extra_time.as_secs(),
+                flow: self.is_flow_session(&self.current_session),
                 session_type: self.current_session.session_type,
+                start_delay: self.current_session.start_delay.as_secs(),
                 status: self.current_session.status,
                 label: self.current_session.label.clone(),
+                step: self.current_session.step,
             },
+            done_for_the_day: self.is_done_for_the_day(),
         }
     }
 }
 
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub struct Session {
-    pub current_time: Seconds,
+    pub current_time: Duration,
+    // Duration of the session when it doesn't follow the config, like the break after a flow
+    // session
+    pub duration: Option<Duration>,
+    // Time added to the duration of the session with `extend`
+    pub extra_time: Duration,
     pub label: Option<String>,
     pub session_type: SessionType,
+    // Time left before a session that started by itself actually starts
+    pub start_delay: Duration,
     pub status: SessionStatus,
+    // Index of the session in `sequence`, always 0 for the classic cycle
+    pub step: usize,
 }
 
 impl Default for Session {
     fn default() -> Self {
         Session {
-            current_time: 0,
+            current_time: Duration::ZERO,
+            duration: None,
+            extra_time: Duration::ZERO,
             label: None,
             session_type: SessionType::Focus,
+            start_delay: Duration::ZERO,
             status: SessionStatus::NotStarted,
+            step: 0,
         }
     }
 }
 
+// The session of the step at `index`, named after the step
+fn step_session(config: &Config, index: usize) -> Option<Session> {
+    let step = config.sequence.get(index)?;
+
+    Some(Session {
+        label: Some(step.name.clone()),
+        session_type: step.session_type,
+        status: if step.auto_start {
+            SessionStatus::Running
+        } else {
+            SessionStatus::NotStarted
+        },
+        step: index,
+        ..Session::default()
+    })
+}
+
+// The first session of the sequence, not started
+fn first_session(config: &Config) -> Session {
+    Session {
+        status: SessionStatus::NotStarted,
+        ..step_session(config, 0).unwrap_or_default()
+    }
+}
+
 pub fn pause(pomodoro: &Pomodoro) -> Pomodoro {
     Pomodoro {
         current_session: Session {
@@ -130,18 +283,20 @@ pub fn pause(pomodoro: &Pomodoro) -> Pomodoro {
             label: pomodoro.current_session.label.clone(),
             ..pomodoro.current_session
         },
-        ..*pomodoro
+        ..pomodoro.clone()
     }
 }
 
+/// Runs the current session, right away if it was waiting for its auto-start delay.
 pub fn play(pomodoro: &Pomodoro) -> Pomodoro {
     Pomodoro {
         current_session: Session {
+            start_delay: Duration::ZERO,
             status: SessionStatus::Running,
             label: pomodoro.current_session.label.clone(),
             ..pomodoro.current_session
         },
-        ..*pomodoro
+        ..pomodoro.clone()
     }
 }
 
@@ -149,32 +304,126 @@ pub fn reset_round(pomodoro: &Pomodoro) -> Pomodoro {
     Pomodoro {
         current_session: Session {
             status: SessionStatus::NotStarted,
-            current_time: 0,
+            current_time: Duration::ZERO,
+            extra_time: Duration::ZERO,
+            start_delay: Duration::ZERO,
             label: pomodoro.current_session.label.clone(),
             ..pomodoro.current_session
         },
-        ..*pomodoro
+        ..pomodoro.clone()
     }
 }
 
 pub fn reset_session(pomodoro: &Pomodoro) -> Pomodoro {
+    let current_session = if pomodoro.config.sequence.is_empty() {
+        Session {
+            label: pomodoro.current_session.label.clone(),
+            ..Session::default()
+        }
+    } else {
+        first_session(&pomodoro.config)
+    };
+
+    Pomodoro {
+        current_session,
+        current_work_round_number: 1,
+        ..pomodoro.clone()
+    }
+}
+
+/// Adds up to `time` to the current session, without making it longer than
+/// `max_session_duration`.
+pub fn extend(pomodoro: &Pomodoro, time: Duration) -> Pomodoro {
+    let duration = pomodoro.duration_of_session(&pomodoro.current_session);
+    let added = time.min(
+        pomodoro
+            .config
+            .max_session_duration
+            .saturating_sub(duration),
+    );
+
     Pomodoro {
         current_session: Session {
-            status: SessionStatus::NotStarted,
-            current_time: 0,
+            extra_time: pomodoro.current_session.extra_time + added,
             label: pomodoro.current_session.label.clone(),
-            session_type: SessionType::Focus,
+            ..pomodoro.current_session
         },
-        current_work_round_number: 1,
-        ..*pomodoro
+        ..pomodoro.clone()
+    }
+}
+
+/// Takes up to `time` off the time elapsed in the current session, like the time the user spent
+/// away from the computer.
+pub fn discard(pomodoro: &Pomodoro, time: Duration) -> Pomodoro {
+    Pomodoro {
+        current_session: Session {
+            current_time: pomodoro.current_session.current_time.saturating_sub(time),
+            label: pomodoro.current_session.label.clone(),
+            ..pomodoro.current_session
+        },
+        ..pomodoro.clone()
+    }
+}
+
+/// Goes back to the session of `ended`, the pomodoro right before its session ended, and runs
+/// it for up to `time` more.
+pub fn reopen(ended: &Pomodoro, time: Duration) -> Pomodoro {
+    extend(&play(ended), time)
+}
+
+// Older states are forgotten past this number
+const UNDO_LIMIT: usize = 20;
+
+/// The states to go back to with `undo`, like before an accidental skip or reset.
+#[derive(PartialEq, Debug, Default, Serialize, Deserialize, Clone)]
+pub struct UndoStack {
+    states: Vec<Pomodoro>,
+}
+
+impl UndoStack {
+    /// Remembers `pomodoro`, to call before changing it.
+    pub fn push(&mut self, pomodoro: &Pomodoro) {
+        self.states.push(pomodoro.clone());
+
+        if self.states.len() > UNDO_LIMIT {
+            self.states.remove(0);
+        }
+    }
+
+    /// The last state pushed, with the config of `current` which may have changed since.
+    pub fn undo(&mut self, current: &Pomodoro) -> Option<Pomodoro> {
+        self.states.pop().map(|previous| Pomodoro {
+            config: current.config.clone(),
+            ..previous
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+}
+
+/// Starts counting the completed focus rounds again, without touching the current session.
+pub fn new_day(pomodoro: &Pomodoro) -> Pomodoro {
+    Pomodoro {
+        completed_focus_rounds: 0,
+        current_session: pomodoro.current_session.clone(),
+        ..pomodoro.clone()
     }
 }
 
 pub fn get_next_session(pomodoro: &Pomodoro) -> Session {
+    let sequence = &pomodoro.config.sequence;
+    if !sequence.is_empty() {
+        let next_step = (pomodoro.current_session.step + 1) % sequence.len();
+        return step_session(&pomodoro.config, next_step).unwrap_or_default();
+    }
+
     let session = pomodoro.current_session.clone();
     match session.session_type {
         SessionType::Focus => {
-            if pomodoro.current_work_round_number == pomodoro.config.max_focus_rounds {
+            // `>=` in case `max_focus_rounds` was lowered during the cycle
+            if pomodoro.current_work_round_number >= pomodoro.config.max_focus_rounds {
                 Session {
                     session_type: SessionType::LongBreak,
                     status: if pomodoro.config.auto_start_long_break_timer {
@@ -210,38 +459,124 @@ pub fn get_next_session(pomodoro: &Pomodoro) -> Session {
 }
 
 pub fn next(pomodoro: &Pomodoro) -> Pomodoro {
+    let next_session = get_next_session(pomodoro);
+    let flow_session = &pomodoro.current_session;
+    let next_session = Session {
+        // The break after a flow session depends on the time spent in it
+        duration: (pomodoro.is_flow_session(flow_session)
+            && !flow_session.current_time.is_zero()
+            && next_session.session_type != SessionType::Focus)
+            .then(|| pomodoro.flow_break()),
+        // Only the sessions that start by themselves wait
+        start_delay: if next_session.status == SessionStatus::Running {
+            pomodoro.config.auto_start_delay
+        } else {
+            Duration::ZERO
+        },
+        ..next_session
+    };
+
     Pomodoro {
-        current_session: get_next_session(pomodoro),
-        current_work_round_number: match pomodoro.current_session.session_type {
-            SessionType::ShortBreak => pomodoro.current_work_round_number + 1,
-            SessionType::LongBreak => 1,
-            _ => pomodoro.current_work_round_number,
+        current_work_round_number: next_round_number(pomodoro, &next_session),
+        current_session: if pomodoro.is_done_for_the_day() {
+            // Once done for the day, nothing starts without the user
+            Session {
+                start_delay: Duration::ZERO,
+                status: SessionStatus::NotStarted,
+                ..next_session
+            }
+        } else {
+            next_session
         },
-        ..*pomodoro
+        ..pomodoro.clone()
+    }
+}
+
+fn next_round_number(pomodoro: &Pomodoro, next_session: &Session) -> u16 {
+    let round = pomodoro.current_work_round_number;
+
+    if pomodoro.config.sequence.is_empty() {
+        return match pomodoro.current_session.session_type {
+            SessionType::ShortBreak => round + 1,
+            SessionType::LongBreak => 1,
+            _ => round,
+        };
+    }
+
+    // A new round starts with each focus step that follows a break, and the count starts over
+    // with the sequence
+    if next_session.step == 0 {
+        1
+    } else if next_session.session_type == SessionType::Focus
+        && pomodoro.current_session.session_type != SessionType::Focus
+    {
+        round + 1
+    } else {
+        round
     }
 }
 
+/// Whether the next tick completes the current session, even if the next one is of the same
+/// type, like with the consecutive focus steps of a sequence.
+pub fn is_last_tick(pomodoro: &Pomodoro) -> bool {
+    let session = &pomodoro.current_session;
+
+    session.status == SessionStatus::Running
+        && session.start_delay.is_zero()
+        && session.current_time + TICK >= pomodoro.duration_of_session(session)
+}
+
 pub fn tick(pomodoro: &Pomodoro) -> Pomodoro {
     let session = pomodoro.current_session.clone();
 
     match session.status {
         // Tick should do something only if the current session is in running mode
+        SessionStatus::Running if !session.start_delay.is_zero() => Pomodoro {
+            current_session: Session {
+                start_delay: session.start_delay.saturating_sub(TICK),
+                ..session
+            },
+            ..pomodoro.clone()
+        },
         SessionStatus::Running => {
             // If it was the last tick, return the next status
-            if session.current_time + 1 == pomodoro.duration_of_session(&session) {
-                return next(pomodoro);
+            // Also when the session got shorter than the time already elapsed
+            if is_last_tick(pomodoro) {
+                return complete(pomodoro);
             }
 
             // If we're not a the end of a session, just update the time of the current session
             Pomodoro {
                 current_session: Session {
-                    current_time: session.current_time + 1,
+                    current_time: session.current_time + TICK,
                     label: session.label,
                     ..pomodoro.current_session
                 },
-                ..*pomodoro
+                ..pomodoro.clone()
             }
         }
         _ => pomodoro.clone(),
     }
 }
+
+// Goes to the next session, the current one counting as completed
+fn complete(pomodoro: &Pomodoro) -> Pomodoro {
+    let completed_focus_rounds = match pomodoro.current_session.session_type {
+        SessionType::Focus => pomodoro.completed_focus_rounds + 1,
+        _ => pomodoro.completed_focus_rounds,
+    };
+
+    next(&Pomodoro {
+        completed_focus_rounds,
+        ..pomodoro.clone()
+    })
+}
+
+/// Ends the current flow session as completed, followed by a break lasting `flow_break_ratio` of
+/// its time. `None` if there is no flow session going on.
+pub fn stop(pomodoro: &Pomodoro) -> Option<Pomodoro> {
+    let session = &pomodoro.current_session;
+
+    (pomodoro.is_flow_session(session) && !session.current_time.is_zero())
+        .then(|| complete(pomodoro))
+}
@@ -0,0 +1,219 @@
+// Pauses the focus sessions while the screen is locked or the computer is asleep
+use crate::pomodoro::{Pomodoro, SessionStatus, SessionType};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemEvent {
+    Lock,
+    Unlock,
+    Sleep,
+    Wake,
+}
+
+impl SystemEvent {
+    // The event the user comes back with after this one
+    fn back(self) -> SystemEvent {
+        match self {
+            SystemEvent::Lock | SystemEvent::Unlock => SystemEvent::Unlock,
+            SystemEvent::Sleep | SystemEvent::Wake => SystemEvent::Wake,
+        }
+    }
+}
+
+/// What to do with the running focus session when the screen gets locked or the computer goes
+/// to sleep.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LockAction {
+    /// Keep it running
+    Nothing,
+    /// Pause it until the screen is unlocked or the computer wakes up
+    #[default]
+    Pause,
+    /// End it like a skip, which can't be undone
+    End,
+}
+
+/// Where the lock and sleep events come from.
+pub trait SystemEventSource {
+    /// The next event, `None` once there won't be any more.
+    fn next_event(&mut self) -> impl Future<Output = Option<SystemEvent>> + Send;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Reaction {
+    Pause,
+    End,
+    /// Start again the session paused by the lock or the sleep
+    Resume,
+}
+
+/// Decides what happens to the sessions on the lock and sleep events.
+#[derive(Debug, Default)]
+pub struct LockWatcher {
+    // The event that paused the session
+    paused_by: Option<SystemEvent>,
+}
+
+impl LockWatcher {
+    pub fn update(
+        &mut self,
+        event: SystemEvent,
+        action: LockAction,
+        pomodoro: &Pomodoro,
+    ) -> Option<Reaction> {
+        let session = &pomodoro.current_session;
+
+        match event {
+            SystemEvent::Lock | SystemEvent::Sleep => {
+                // Going to sleep once the screen is locked does nothing more, it is already paused
+                if session.session_type != SessionType::Focus
+                    || session.status != SessionStatus::Running
+                {
+                    return None;
+                }
+
+                match action {
+                    LockAction::Nothing => None,
+                    LockAction::Pause => {
+                        self.paused_by = Some(event);
+                        Some(Reaction::Pause)
+                    }
+                    LockAction::End => {
+                        self.paused_by = None;
+                        Some(Reaction::End)
+                    }
+                }
+            }
+            SystemEvent::Unlock | SystemEvent::Wake => {
+                if self.paused_by.map(SystemEvent::back) != Some(event) {
+                    return None;
+                }
+
+                self.paused_by = None;
+                // Unless the user did something else with the timer in the meantime
+                (session.status == SessionStatus::Paused).then_some(Reaction::Resume)
+            }
+        }
+    }
+}
+
+/// The lock and sleep events of logind, on Linux.
+pub struct DesktopEventSource {
+    #[cfg(target_os = "linux")]
+    messages: Option<futures::stream::SelectAll<zbus::MessageStream>>,
+}
+
+impl DesktopEventSource {
+    #[cfg(target_os = "linux")]
+    pub async fn new() -> Self {
+        let messages = match logind::messages().await {
+            Ok(messages) => Some(messages),
+            Err(e) => {
+                eprintln!("Unable to follow the screen lock and the sleep with logind: {e:?}.");
+                None
+            }
+        };
+
+        DesktopEventSource { messages }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub async fn new() -> Self {
+        DesktopEventSource {}
+    }
+}
+
+impl SystemEventSource for DesktopEventSource {
+    #[cfg(target_os = "linux")]
+    async fn next_event(&mut self) -> Option<SystemEvent> {
+        logind::next_event(self.messages.as_mut()?).await
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn next_event(&mut self) -> Option<SystemEvent> {
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod logind {
+    use super::SystemEvent;
+    use futures::StreamExt;
+    use futures::stream::SelectAll;
+    use zbus::zvariant::OwnedObjectPath;
+    use zbus::{Connection, MatchRule, MessageStream};
+
+    const DESTINATION: &str = "org.freedesktop.login1";
+    const MANAGER_PATH: &str = "/org/freedesktop/login1";
+    const MANAGER_INTERFACE: &str = "org.freedesktop.login1.Manager";
+    const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+
+    pub async fn messages() -> zbus::Result<SelectAll<MessageStream>> {
+        let connection = Connection::system().await?;
+
+        let sleep_rule = MatchRule::builder()
+            .msg_type(zbus::message::Type::Signal)
+            .interface(MANAGER_INTERFACE)?
+            .member("PrepareForSleep")?
+            .build();
+        let mut streams = vec![MessageStream::for_match_rule(sleep_rule, &connection, None).await?];
+
+        // Only the lock of the session the app runs in, not the ones of the other users
+        match session_path(&connection).await {
+            Ok(session_path) => {
+                let lock_rule = MatchRule::builder()
+                    .msg_type(zbus::message::Type::Signal)
+                    .interface(SESSION_INTERFACE)?
+                    .path(session_path.as_ref())?
+                    .build();
+                streams.push(MessageStream::for_match_rule(lock_rule, &connection, None).await?);
+            }
+            Err(e) => eprintln!("Unable to find the logind session to follow its lock: {e:?}."),
+        }
+
+        Ok(futures::stream::select_all(streams))
+    }
+
+    async fn session_path(connection: &Connection) -> zbus::Result<OwnedObjectPath> {
+        let reply = connection
+            .call_method(
+                Some(DESTINATION),
+                MANAGER_PATH,
+                Some(MANAGER_INTERFACE),
+                "GetSession",
+                &("auto",),
+            )
+            .await?;
+
+        reply.body().deserialize()
+    }
+
+    pub async fn next_event(messages: &mut SelectAll<MessageStream>) -> Option<SystemEvent> {
+        while let Some(message) = messages.next().await {
+            let Ok(message) = message else {
+                continue;
+            };
+
+            let header = message.header();
+            let event = match header.member().map(|member| member.as_str()) {
+                Some("Lock") => SystemEvent::Lock,
+                Some("Unlock") => SystemEvent::Unlock,
+                // `true` before going to sleep, `false` when waking up
+                Some("PrepareForSleep") => match message.body().deserialize() {
+                    Ok(true) => SystemEvent::Sleep,
+                    Ok(false) => SystemEvent::Wake,
+                    Err(_) => continue,
+                },
+                _ => continue,
+            };
+
+            return Some(event);
+        }
+
+        None
+    }
+}
@@ -0,0 +1,169 @@
+use crate::color::{self, Interpolation, Rgb};
+use crate::pomodoro::SessionType;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Colors {
+    pub accent: String,
+    pub background: String,
+    pub background_light: String,
+    pub background_lightest: String,
+    pub focus_round: String,
+    pub focus_round_middle: String,
+    pub focus_round_end: String,
+    pub foreground: String,
+    pub foreground_darker: String,
+    pub foreground_darkest: String,
+    pub long_round: String,
+    pub short_round: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Theme {
+    pub colors: Colors,
+    pub name: String,
+}
+
+impl Theme {
+    /// Color of a session of type `session_type`, `progress` going from 0 (just started) to 1 (ended).
+    /// Focus sessions go through the focus round gradient, breaks use a single color.
+    pub fn color_for(
+        &self,
+        session_type: SessionType,
+        progress: f32,
+        interpolation: Interpolation,
+    ) -> Option<Rgb> {
+        match session_type {
+            SessionType::Focus => {
+                let stops = [
+                    &self.colors.focus_round,
+                    &self.colors.focus_round_middle,
+                    &self.colors.focus_round_end,
+                ]
+                .map(|hex| Rgb::parse(hex));
+
+                color::gradient(
+                    &stops.into_iter().collect::<Option<Vec<_>>>()?,
+                    progress,
+                    interpolation,
+                )
+            }
+            SessionType::ShortBreak => Rgb::parse(&self.colors.short_round),
+            SessionType::LongBreak => Rgb::parse(&self.colors.long_round),
+        }
+    }
+}
+
+impl From<JsonTheme> for Theme {
+    fn from(json_theme: JsonTheme) -> Self {
+        let (focus_round_middle, focus_round_end) = match (
+            json_theme.colors.focus_round_middle,
+            json_theme.colors.focus_round_end,
+        ) {
+            (Some(middle), Some(end)) => (middle, end),
+            // If middle or end are not provided, try to compute the middle color ourself
+            // It will be the middle gradient between the focus round color and the short round
+            // color
+            _ => match color::mix(
+                json_theme.colors.short_round.as_str(),
+                json_theme.colors.focus_round.as_str(),
+                0.5,
+                Interpolation::Rgb,
+            ) {
+                Some(middle) => (middle, json_theme.colors.short_round.clone()),
+                None => (
+                    json_theme.colors.focus_round.clone(),
+                    json_theme.colors.focus_round.clone(),
+                ),
+            },
+        };
+
+        Theme {
+            colors: Colors {
+                accent: json_theme.colors.accent,
+                background: json_theme.colors.background,
+                background_light: json_theme.colors.background_light,
+                background_lightest: json_theme.colors.background_lightest,
+                focus_round: json_theme.colors.focus_round,
+                focus_round_middle,
+                focus_round_end,
+                foreground: json_theme.colors.foreground,
+                foreground_darker: json_theme.colors.foreground_darker,
+                foreground_darkest: json_theme.colors.foreground_darkest,
+                long_round: json_theme.colors.long_round,
+                short_round: json_theme.colors.short_round,
+            },
+            name: json_theme.name,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct JsonColors {
+    /// Color of the buttons and of the selected items
+    #[serde(rename = "--color-accent")]
+    #[schemars(pattern(HEX_COLOR))]
+    pub accent: String,
+    /// Background of the window
+    #[serde(rename = "--color-background")]
+    #[schemars(pattern(HEX_COLOR))]
+    pub background: String,
+    /// Background of the drawer and of the inputs
+    #[serde(rename = "--color-background-light")]
+    #[schemars(pattern(HEX_COLOR))]
+    pub background_light: String,
+    /// Background of the timer dial and of the sliders
+    #[serde(rename = "--color-background-lightest")]
+    #[schemars(pattern(HEX_COLOR))]
+    pub background_lightest: String,
+    /// Color of the timer at the start of the focus sessions
+    #[serde(rename = "--color-focus-round")]
+    #[schemars(pattern(HEX_COLOR))]
+    pub focus_round: String,
+    /// Color of the timer in the middle of the focus sessions, computed if not set
+    #[serde(
+        rename = "--color-focus-round-middle",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schemars(pattern(HEX_COLOR))]
+    pub focus_round_middle: Option<String>,
+    /// Color of the timer at the end of the focus sessions, computed if not set
+    #[serde(
+        rename = "--color-focus-round-end",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schemars(pattern(HEX_COLOR))]
+    pub focus_round_end: Option<String>,
+    /// Main text color
+    #[serde(rename = "--color-foreground")]
+    #[schemars(pattern(HEX_COLOR))]
+    pub foreground: String,
+    /// Color of the secondary texts
+    #[serde(rename = "--color-foreground-darker")]
+    #[schemars(pattern(HEX_COLOR))]
+    pub foreground_darker: String,
+    /// Color of the disabled items and of the least important texts
+    #[serde(rename = "--color-foreground-darkest")]
+    #[schemars(pattern(HEX_COLOR))]
+    pub foreground_darkest: String,
+    /// Color of the timer during long breaks
+    #[serde(rename = "--color-long-round")]
+    #[schemars(pattern(HEX_COLOR))]
+    pub long_round: String,
+    /// Color of the timer during short breaks
+    #[serde(rename = "--color-short-round")]
+    #[schemars(pattern(HEX_COLOR))]
+    pub short_round: String,
+}
+
+// Colors accepted by `Rgb::parse`: #RGB, #RGBA, #RRGGBB or #RRGGBBAA.
+pub(crate) const HEX_COLOR: &str = "^#([0-9a-fA-F]{3,4}|[0-9a-fA-F]{6}|[0-9a-fA-F]{8})$";
+
+/// Theme file, in the format of the Pomotroid themes.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct JsonTheme {
+    pub colors: JsonColors,
+    /// Name of the theme, shown in the settings
+    pub name: String,
+}
@@ -0,0 +1,284 @@
+use crate::color::{self, Interpolation};
+use crate::theme::{JsonColors, JsonTheme};
+use clap::ValueEnum;
+use hex_color::HexColor;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum ThemeFormat {
+    /// Pomotroid theme JSON
+    Pomotroid,
+    /// base16 scheme YAML
+    Base16,
+    /// Alacritty TOML color configuration
+    Alacritty,
+    /// Kitty color configuration
+    Kitty,
+}
+
+impl ThemeFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(ThemeFormat::Pomotroid),
+            "yaml" | "yml" => Some(ThemeFormat::Base16),
+            "toml" => Some(ThemeFormat::Alacritty),
+            "conf" => Some(ThemeFormat::Kitty),
+            _ => None,
+        }
+    }
+}
+
+// The colors of a terminal color scheme we need to build a theme
+struct TerminalColors {
+    background: String,
+    foreground: String,
+    red: String,
+    green: String,
+    cyan: String,
+    magenta: String,
+    bright_black: String,
+}
+
+impl TerminalColors {
+    fn into_json_theme(self, name: String) -> Result<JsonTheme, String> {
+        let mix_or_err = |from: &str, to: &str, t: f32| {
+            color::mix(from, to, t, Interpolation::Rgb)
+                .ok_or(format!("Unable to mix colors {from} and {to}."))
+        };
+
+        Ok(JsonTheme {
+            colors: JsonColors {
+                accent: self.magenta,
+                background_light: mix_or_err(&self.background, &self.foreground, 0.1)?,
+                background_lightest: self.bright_black,
+                // `focus_round_middle` and `focus_round_end` are computed when the theme is loaded
+                focus_round: self.red,
+                focus_round_middle: None,
+                focus_round_end: None,
+                foreground_darker: mix_or_err(&self.foreground, &self.background, 0.15)?,
+                foreground_darkest: mix_or_err(&self.foreground, &self.background, 0.3)?,
+                background: self.background,
+                foreground: self.foreground,
+                long_round: self.cyan,
+                short_round: self.green,
+            },
+            name,
+        })
+    }
+}
+
+pub fn import_file(
+    path: &Path,
+    format: Option<ThemeFormat>,
+    name: Option<String>,
+) -> Result<JsonTheme, String> {
+    let format = format
+        .or_else(|| ThemeFormat::from_path(path))
+        .ok_or(format!(
+            "Unable to guess the format of {path:?}, please specify it with --format."
+        ))?;
+
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Unable to read {path:?}: {e:?}."))?;
+
+    let file_stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or("Imported theme".to_string());
+
+    let theme = import(&content, format, file_stem)?;
+
+    Ok(match name {
+        Some(name) => JsonTheme { name, ..theme },
+        None => theme,
+    })
+}
+
+/// Converts the content of a color scheme file to a theme.
+/// `default_name` is used if the file doesn't provide a name itself.
+pub fn import(
+    content: &str,
+    format: ThemeFormat,
+    default_name: String,
+) -> Result<JsonTheme, String> {
+    match format {
+        ThemeFormat::Pomotroid => import_pomotroid(content),
+        ThemeFormat::Base16 => import_base16(content, default_name),
+        ThemeFormat::Alacritty => import_alacritty(content, default_name),
+        ThemeFormat::Kitty => import_kitty(content, default_name),
+    }
+}
+
+fn import_pomotroid(content: &str) -> Result<JsonTheme, String> {
+    let mut theme: JsonTheme =
+        serde_json::from_str(content).map_err(|e| format!("Invalid Pomotroid theme: {e:?}."))?;
+
+    let colors = &mut theme.colors;
+    for color in [
+        &mut colors.accent,
+        &mut colors.background,
+        &mut colors.background_light,
+        &mut colors.background_lightest,
+        &mut colors.focus_round,
+        &mut colors.foreground,
+        &mut colors.foreground_darker,
+        &mut colors.foreground_darkest,
+        &mut colors.long_round,
+        &mut colors.short_round,
+    ]
+    .into_iter()
+    .chain(colors.focus_round_middle.as_mut())
+    .chain(colors.focus_round_end.as_mut())
+    {
+        *color = normalize_hex(color).ok_or(format!("Invalid color `{color}`."))?;
+    }
+
+    Ok(theme)
+}
+
+fn import_base16(content: &str, default_name: String) -> Result<JsonTheme, String> {
+    // base16 schemes are flat `key: value` files (the newer format nests the colors under
+    // `palette:`), so there is no need for a full YAML parser
+    let values: HashMap<String, String> = content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            Some((key.trim().to_lowercase(), yaml_scalar(value).to_string()))
+        })
+        .collect();
+
+    let color = |key: &str| {
+        values
+            .get(key)
+            .and_then(|value| normalize_hex(value))
+            .ok_or(format!("Missing or invalid `{key}` color."))
+    };
+
+    let name = values
+        .get("scheme")
+        .or(values.get("name"))
+        .filter(|name| !name.is_empty())
+        .cloned()
+        .unwrap_or(default_name);
+
+    Ok(JsonTheme {
+        colors: JsonColors {
+            accent: color("base0e")?,
+            background: color("base00")?,
+            background_light: color("base01")?,
+            background_lightest: color("base02")?,
+            focus_round: color("base08")?,
+            focus_round_middle: None,
+            focus_round_end: None,
+            foreground: color("base05")?,
+            foreground_darker: color("base04")?,
+            foreground_darkest: color("base03")?,
+            long_round: color("base0c")?,
+            short_round: color("base0b")?,
+        },
+        name,
+    })
+}
+
+// The value of a `key: value` YAML line, without its quotes or its trailing comment
+fn yaml_scalar(value: &str) -> &str {
+    let value = value.trim();
+
+    if let Some(quote) = value.chars().next().filter(|c| ['"', '\''].contains(c)) {
+        let quoted = &value[1..];
+        return quoted.find(quote).map_or(quoted, |end| &quoted[..end]);
+    }
+
+    // A comment starts with a `#` after a space, but an unquoted color like `#181818` is taken as
+    // the value rather than as an empty value followed by a comment
+    if value.starts_with('#') {
+        return value.split_whitespace().next().unwrap_or_default();
+    }
+    let end = value
+        .char_indices()
+        .find(|&(i, c)| c == '#' && value[..i].ends_with(char::is_whitespace))
+        .map_or(value.len(), |(i, _)| i);
+    value[..end].trim_end()
+}
+
+fn import_alacritty(content: &str, default_name: String) -> Result<JsonTheme, String> {
+    let value: toml::Value =
+        toml::from_str(content).map_err(|e| format!("Invalid Alacritty config: {e:?}."))?;
+
+    let color = |section: &str, key: &str| {
+        value
+            .get("colors")
+            .and_then(|colors| colors.get(section))
+            .and_then(|section| section.get(key))
+            .and_then(|color| color.as_str())
+            .and_then(normalize_hex)
+            .ok_or(format!(
+                "Missing or invalid `colors.{section}.{key}` color."
+            ))
+    };
+
+    TerminalColors {
+        background: color("primary", "background")?,
+        foreground: color("primary", "foreground")?,
+        red: color("normal", "red")?,
+        green: color("normal", "green")?,
+        cyan: color("normal", "cyan")?,
+        magenta: color("normal", "magenta")?,
+        bright_black: color("bright", "black")?,
+    }
+    .into_json_theme(default_name)
+}
+
+fn import_kitty(content: &str, default_name: String) -> Result<JsonTheme, String> {
+    let mut name = None;
+    let mut values: HashMap<&str, &str> = HashMap::new();
+
+    for line in content.lines().map(str::trim) {
+        // Kitty themes store their metadata in comments like `## name: Dracula`
+        if let Some(metadata) = line.strip_prefix("##") {
+            if let Some((key, value)) = metadata.split_once(':')
+                && key.trim() == "name"
+            {
+                name = Some(value.trim().to_string());
+            }
+        } else if !line.starts_with('#')
+            && let Some((key, value)) = line.split_once(char::is_whitespace)
+        {
+            values.insert(key, value.trim());
+        }
+    }
+
+    let color = |key: &str| {
+        values
+            .get(key)
+            .and_then(|value| normalize_hex(value))
+            .ok_or(format!("Missing or invalid `{key}` color."))
+    };
+
+    TerminalColors {
+        background: color("background")?,
+        foreground: color("foreground")?,
+        red: color("color1")?,
+        green: color("color2")?,
+        cyan: color("color6")?,
+        magenta: color("color5")?,
+        bright_black: color("color8")?,
+    }
+    .into_json_theme(name.unwrap_or(default_name))
+}
+
+// Accepts `#rrggbb`, `0xrrggbb` and `rrggbb` colors and returns them as `#RRGGBB`
+fn normalize_hex(value: &str) -> Option<String> {
+    let value = value.trim();
+    let hex = value
+        .strip_prefix('#')
+        .or(value.strip_prefix("0x"))
+        .unwrap_or(value);
+
+    HexColor::parse_rgb(format!("#{hex}").as_str())
+        .ok()
+        .map(|color| color.display_rgb().to_string())
+}
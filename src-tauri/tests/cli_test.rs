@@ -0,0 +1,107 @@
+use pomodorolm_lib::cli;
+use pomodorolm_lib::config::{Config, ConfigSource, Overrides};
+use std::time::Duration;
+
+fn config_source(config_dir: &tempfile::TempDir) -> ConfigSource {
+    ConfigSource::new(
+        "pomodorolm",
+        Some(config_dir.path().to_path_buf()),
+        None,
+        Overrides::default(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn it_sets_settings_with_human_durations() {
+    let config_dir = tempfile::tempdir().unwrap();
+    let config_source = config_source(&config_dir);
+
+    cli::set_setting(&config_source, "focus_duration", "50m").unwrap();
+    cli::set_setting(&config_source, "theme", "dracula").unwrap();
+    cli::set_setting(&config_source, "notification_timeout", "30").unwrap();
+
+    assert_eq!(
+        cli::get_setting(&config_source, "focus_duration").unwrap(),
+        "50m"
+    );
+    assert_eq!(
+        cli::get_setting(&config_source, "theme").unwrap(),
+        "dracula"
+    );
+    assert_eq!(
+        cli::get_setting(&config_source, "notification_timeout").unwrap(),
+        "30s"
+    );
+    assert!(
+        cli::list_settings(&config_source)
+            .unwrap()
+            .contains(&("focus_duration".to_string(), "50m".to_string()))
+    );
+}
+
+#[test]
+fn it_does_not_set_invalid_settings() {
+    let config_dir = tempfile::tempdir().unwrap();
+    let config_source = config_source(&config_dir);
+
+    assert!(cli::set_setting(&config_source, "focus_duration", "soon").is_err());
+    assert!(cli::set_setting(&config_source, "focus_duration", "0m").is_err());
+    assert!(cli::set_setting(&config_source, "always_on_top", "maybe").is_err());
+    assert!(cli::set_setting(&config_source, "unknown_setting", "1").is_err());
+
+    assert_eq!(
+        config_source.load_file().unwrap().focus_duration,
+        Config::default().focus_duration
+    );
+}
+
+#[test]
+fn it_resets_settings() {
+    let config_dir = tempfile::tempdir().unwrap();
+    let config_source = config_source(&config_dir);
+
+    cli::set_setting(&config_source, "focus_duration", "50m").unwrap();
+    cli::set_setting(&config_source, "theme_dark", "dracula").unwrap();
+
+    cli::reset_settings(&config_source, Some("theme_dark")).unwrap();
+    let config = config_source.load_file().unwrap();
+    assert_eq!(config.theme_dark, None);
+    assert_eq!(config.focus_duration, Duration::from_secs(50 * 60));
+
+    cli::set_setting(
+        &config_source,
+        "sequence",
+        "[{ name = \"Write\", duration = \"45m\" }]",
+    )
+    .unwrap();
+
+    cli::reset_settings(&config_source, None).unwrap();
+    let config = config_source.load_file().unwrap();
+    assert_eq!(config.focus_duration, Config::default().focus_duration);
+    // Written by hand, they are kept
+    assert_eq!(config.sequence_steps().len(), 1);
+}
+
+#[test]
+fn it_generates_the_json_schemas() {
+    let schema: serde_json::Value =
+        serde_json::from_str(&cli::schema(cli::SchemaKind::Config).unwrap()).unwrap();
+
+    let properties = &schema["properties"];
+    assert_eq!(properties["focus_duration"]["$ref"], "#/$defs/Duration");
+    assert_eq!(properties["volume"]["maximum"], 100);
+    assert!(
+        properties["minimize_to_tray"]["description"]
+            .as_str()
+            .is_some()
+    );
+
+    let theme_schema: serde_json::Value =
+        serde_json::from_str(&cli::schema(cli::SchemaKind::Theme).unwrap()).unwrap();
+
+    assert_eq!(
+        theme_schema["required"],
+        serde_json::json!(["colors", "name"])
+    );
+}
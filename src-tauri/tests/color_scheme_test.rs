@@ -0,0 +1,64 @@
+use pomodorolm_lib::color_scheme::{self, ColorScheme};
+use pomodorolm_lib::config::Config;
+
+#[test]
+fn it_follows_the_time_of_day_when_the_desktop_has_no_preference() {
+    assert_eq!(color_scheme::from_time_of_day(0), ColorScheme::Dark);
+    assert_eq!(color_scheme::from_time_of_day(6), ColorScheme::Dark);
+    assert_eq!(color_scheme::from_time_of_day(7), ColorScheme::Light);
+    assert_eq!(color_scheme::from_time_of_day(18), ColorScheme::Light);
+    assert_eq!(color_scheme::from_time_of_day(19), ColorScheme::Dark);
+    assert_eq!(color_scheme::from_time_of_day(23), ColorScheme::Dark);
+}
+
+#[test]
+fn it_picks_the_theme_of_the_color_scheme() {
+    let config = Config {
+        theme: "pomodorolm".to_string(),
+        ..Config::default()
+    };
+    // No automatic switching
+    assert_eq!(config.theme_for_color_scheme(ColorScheme::Light), None);
+    assert_eq!(config.theme_for_color_scheme(ColorScheme::Dark), None);
+
+    let config = Config {
+        theme_light: Some("github".to_string()),
+        theme_dark: Some("dracula".to_string()),
+        ..config
+    };
+    assert_eq!(
+        config.theme_for_color_scheme(ColorScheme::Light),
+        Some("github")
+    );
+    assert_eq!(
+        config.theme_for_color_scheme(ColorScheme::Dark),
+        Some("dracula")
+    );
+
+    // `theme` stands for the one that isn't set
+    let only_light = Config {
+        theme_dark: None,
+        ..config.clone()
+    };
+    assert_eq!(
+        only_light.theme_for_color_scheme(ColorScheme::Light),
+        Some("github")
+    );
+    assert_eq!(
+        only_light.theme_for_color_scheme(ColorScheme::Dark),
+        Some("pomodorolm")
+    );
+
+    let only_dark = Config {
+        theme_light: None,
+        ..config
+    };
+    assert_eq!(
+        only_dark.theme_for_color_scheme(ColorScheme::Light),
+        Some("pomodorolm")
+    );
+    assert_eq!(
+        only_dark.theme_for_color_scheme(ColorScheme::Dark),
+        Some("dracula")
+    );
+}
@@ -0,0 +1,127 @@
+use pomodorolm_lib::color::{self, Interpolation, Rgb};
+
+const BLACK: Rgb = Rgb {
+    red: 0,
+    green: 0,
+    blue: 0,
+};
+
+const WHITE: Rgb = Rgb {
+    red: 255,
+    green: 255,
+    blue: 255,
+};
+
+const RED: Rgb = Rgb {
+    red: 255,
+    green: 0,
+    blue: 0,
+};
+
+const BLUE: Rgb = Rgb {
+    red: 0,
+    green: 0,
+    blue: 255,
+};
+
+#[test]
+fn it_parses_and_formats_hex_colors() {
+    assert_eq!(
+        Rgb::parse("#ff4e4d"),
+        Some(Rgb {
+            red: 255,
+            green: 78,
+            blue: 77
+        })
+    );
+    assert_eq!(Rgb::parse("not a color"), None);
+    assert_eq!(RED.to_hex(), "#FF0000");
+}
+
+#[test]
+fn interpolation_should_start_and_end_on_the_given_colors() {
+    for interpolation in [Interpolation::Rgb, Interpolation::Hsl, Interpolation::Oklab] {
+        assert_eq!(color::interpolate(RED, BLUE, 0.0, interpolation), RED);
+        assert_eq!(color::interpolate(RED, BLUE, 1.0, interpolation), BLUE);
+    }
+}
+
+#[test]
+fn rgb_interpolation_should_be_linear() {
+    assert_eq!(
+        color::mix("#000000", "#FFFFFF", 0.5, Interpolation::Rgb),
+        Some("#808080".to_string())
+    );
+}
+
+#[test]
+fn hsl_interpolation_should_go_through_the_hue_circle() {
+    // Between red and blue, the shortest path on the hue circle goes through magenta
+    assert_eq!(
+        color::interpolate(RED, BLUE, 0.5, Interpolation::Hsl),
+        Rgb {
+            red: 255,
+            green: 0,
+            blue: 255
+        }
+    );
+}
+
+#[test]
+fn hsl_interpolation_should_keep_the_hue_of_the_color_next_to_a_grey() {
+    // No detour through the pinks between white and blue
+    assert_eq!(
+        color::interpolate(WHITE, BLUE, 0.5, Interpolation::Hsl),
+        Rgb {
+            red: 159,
+            green: 159,
+            blue: 223
+        }
+    );
+    assert_eq!(
+        color::interpolate(BLUE, BLACK, 0.5, Interpolation::Hsl),
+        Rgb {
+            red: 32,
+            green: 32,
+            blue: 96
+        }
+    );
+}
+
+#[test]
+fn oklab_interpolation_should_use_the_perceptual_lightness() {
+    // OKLab lightness 0.5 is the sRGB gray #636363, not the RGB middle #808080
+    let middle = color::interpolate(BLACK, WHITE, 0.5, Interpolation::Oklab);
+
+    assert_eq!(
+        middle,
+        Rgb {
+            red: 99,
+            green: 99,
+            blue: 99
+        }
+    );
+}
+
+#[test]
+fn gradient_should_go_through_all_the_stops() {
+    let stops = [RED, WHITE, BLUE];
+
+    assert_eq!(color::gradient(&stops, 0.0, Interpolation::Rgb), Some(RED));
+    assert_eq!(
+        color::gradient(&stops, 0.5, Interpolation::Rgb),
+        Some(WHITE)
+    );
+    assert_eq!(color::gradient(&stops, 1.0, Interpolation::Rgb), Some(BLUE));
+    assert_eq!(
+        color::gradient(&stops, 0.25, Interpolation::Rgb),
+        Some(Rgb {
+            red: 255,
+            green: 128,
+            blue: 128
+        })
+    );
+
+    assert_eq!(color::gradient(&[RED], 0.7, Interpolation::Rgb), Some(RED));
+    assert_eq!(color::gradient(&[], 0.7, Interpolation::Rgb), None);
+}
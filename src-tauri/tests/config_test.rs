@@ -0,0 +1,520 @@
+use pomodorolm_lib::config::{
+    CONFIG_VERSION, Config, ConfigError, ConfigSource, Overrides, Profile, Sequence, SequenceStep,
+};
+use pomodorolm_lib::hooks::Hooks;
+use pomodorolm_lib::pomodoro::SessionType;
+use std::fs;
+use std::time::Duration;
+
+fn minutes(minutes: u64) -> Duration {
+    Duration::from_secs(minutes * 60)
+}
+
+#[test]
+fn it_migrates_unversioned_configs() {
+    let (config, migrated_from) = Config::parse(
+        "
+always_on_top = false
+pomodoro_duration = 1800
+",
+    )
+    .unwrap();
+
+    assert_eq!(migrated_from, Some(0));
+    assert_eq!(config.version, CONFIG_VERSION);
+    assert!(!config.always_on_top);
+    // `pomodoro_duration` was renamed to `focus_duration`
+    assert_eq!(config.focus_duration, minutes(30));
+    // Missing fields get their default value
+    assert_eq!(config.max_round_number, Config::default().max_round_number);
+}
+
+#[test]
+fn it_does_not_migrate_up_to_date_configs() {
+    let toml_str = toml::to_string(&Config::default()).unwrap();
+    let (config, migrated_from) = Config::parse(&toml_str).unwrap();
+
+    assert_eq!(migrated_from, None);
+    assert_eq!(config.version, CONFIG_VERSION);
+}
+
+#[test]
+fn it_rejects_invalid_configs() {
+    assert!(matches!(
+        Config::parse("focus_duration = "),
+        Err(ConfigError::Parse(_))
+    ));
+    assert!(matches!(
+        Config::parse("focus_duration = \"25 minutes\""),
+        Err(ConfigError::Parse(_))
+    ));
+    assert!(matches!(
+        Config::parse(&format!("version = {}", CONFIG_VERSION + 1)),
+        Err(ConfigError::UnsupportedVersion(_))
+    ));
+}
+
+#[test]
+fn it_keeps_a_backup_of_migrated_configs() {
+    let config_dir = tempfile::tempdir().unwrap();
+    let config_file_path = Config::get_config_file_path(config_dir.path(), None);
+    fs::write(&config_file_path, "pomodoro_duration = 1800\n").unwrap();
+
+    let config = Config::get_or_create_from_disk(config_dir.path(), None).unwrap();
+    assert_eq!(config.focus_duration, minutes(30));
+
+    let backup = fs::read_to_string(config_dir.path().join("config.toml.v0.bak")).unwrap();
+    assert_eq!(backup, "pomodoro_duration = 1800\n");
+
+    let (migrated, migrated_from) =
+        Config::parse(&fs::read_to_string(&config_file_path).unwrap()).unwrap();
+    assert_eq!(migrated_from, None);
+    assert_eq!(migrated.focus_duration, minutes(30));
+}
+
+#[test]
+fn it_validates_configs() {
+    assert!(Config::default().validate().is_ok());
+
+    let config = Config {
+        focus_duration: Duration::ZERO,
+        ..Config::default()
+    };
+    assert!(matches!(
+        config.validate(),
+        Err(ConfigError::Invalid {
+            field: "focus_duration",
+            ..
+        })
+    ));
+
+    let config = Config {
+        max_session_duration: minutes(60),
+        long_break_duration: minutes(120),
+        ..Config::default()
+    };
+    assert!(matches!(
+        config.validate(),
+        Err(ConfigError::Invalid {
+            field: "long_break_duration",
+            ..
+        })
+    ));
+
+    let config = Config {
+        max_round_number: 0,
+        ..Config::default()
+    };
+    assert!(matches!(
+        config.validate(),
+        Err(ConfigError::Invalid {
+            field: "max_round_number",
+            ..
+        })
+    ));
+
+    for flow_break_ratio in [0.0, 1.5, f32::NAN] {
+        let config = Config {
+            flow_break_ratio,
+            ..Config::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid {
+                field: "flow_break_ratio",
+                ..
+            })
+        ));
+    }
+
+    let config = Config {
+        hooks: Hooks {
+            timeout: Duration::ZERO,
+            ..Hooks::default()
+        },
+        ..Config::default()
+    };
+    assert!(matches!(
+        config.validate(),
+        Err(ConfigError::Invalid { field: "hooks", .. })
+    ));
+
+    let config = Config {
+        http_api_port: Some(0),
+        http_api_token: Some("secret".to_string()),
+        ..Config::default()
+    };
+    assert!(matches!(
+        config.validate(),
+        Err(ConfigError::Invalid {
+            field: "http_api_port",
+            ..
+        })
+    ));
+
+    let config = Config {
+        http_api_port: Some(7531),
+        ..Config::default()
+    };
+    assert!(matches!(
+        config.validate(),
+        Err(ConfigError::Invalid {
+            field: "http_api_token",
+            ..
+        })
+    ));
+
+    let config = Config {
+        idle_pause_after: Some(Duration::ZERO),
+        ..Config::default()
+    };
+    assert!(matches!(
+        config.validate(),
+        Err(ConfigError::Invalid {
+            field: "idle_pause_after",
+            ..
+        })
+    ));
+
+    let config = Config {
+        volume: Some(101),
+        ..Config::default()
+    };
+    assert!(matches!(
+        config.validate(),
+        Err(ConfigError::Invalid {
+            field: "volume",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn it_reads_the_sequences() {
+    let default_toml = toml::to_string(&Config::default()).unwrap();
+    assert!(default_toml.contains("sequence = \"classic\""));
+    assert!(Config::default().sequence_steps().is_empty());
+
+    let (config, _) =
+        Config::parse(&default_toml.replace("sequence = \"classic\"", "sequence = \"52-17\""))
+            .unwrap();
+    let steps = config.sequence_steps();
+    assert_eq!(
+        steps
+            .iter()
+            .map(|step| (step.kind, step.duration))
+            .collect::<Vec<_>>(),
+        [
+            (SessionType::Focus, minutes(52)),
+            (SessionType::ShortBreak, minutes(17))
+        ]
+    );
+
+    let lunch = SequenceStep {
+        audio: Some("/tmp/gong.mp3".to_string()),
+        auto_start: true,
+        color: Some("#ffaa00".to_string()),
+        duration: minutes(30),
+        kind: SessionType::LongBreak,
+        name: "Lunch".to_string(),
+    };
+    let config = Config {
+        sequence: Sequence::Steps(vec![lunch.clone()]),
+        ..Config::default()
+    };
+    let (parsed, _) = Config::parse(&toml::to_string(&config).unwrap()).unwrap();
+    assert_eq!(parsed.sequence, config.sequence);
+
+    for (sequence, expected) in [
+        (Sequence::Steps(vec![]), "at least one step"),
+        (
+            Sequence::Steps(vec![SequenceStep {
+                duration: minutes(180),
+                ..lunch.clone()
+            }]),
+            "step `Lunch`",
+        ),
+        (
+            Sequence::Steps(vec![SequenceStep {
+                color: Some("orange".to_string()),
+                ..lunch.clone()
+            }]),
+            "invalid color",
+        ),
+    ] {
+        let config = Config {
+            sequence,
+            ..Config::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid { field: "sequence", message }) if message.contains(expected)
+        ));
+    }
+}
+
+#[test]
+fn it_does_not_overwrite_the_config_with_an_invalid_one() {
+    let config_dir = tempfile::tempdir().unwrap();
+    let config_file_path = Config::get_config_file_path(config_dir.path(), None);
+    Config::default().save(&config_file_path).unwrap();
+
+    let invalid_config = Config {
+        short_break_duration: Duration::ZERO,
+        ..Config::default()
+    };
+    assert!(invalid_config.save(&config_file_path).is_err());
+
+    let (config, _) = Config::parse(&fs::read_to_string(&config_file_path).unwrap()).unwrap();
+    assert_eq!(
+        config.short_break_duration,
+        Config::default().short_break_duration
+    );
+    // No temporary file is left behind
+    assert_eq!(fs::read_dir(config_dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn it_rejects_the_invalid_values_of_the_hand_edited_files() {
+    let config_dir = tempfile::tempdir().unwrap();
+    let config_source = ConfigSource::new(
+        "pomodorolm",
+        Some(config_dir.path().to_path_buf()),
+        None,
+        Overrides::default(),
+    )
+    .unwrap();
+    let invalid_config = Config {
+        volume: Some(150),
+        ..Config::default()
+    };
+    fs::write(
+        &config_source.config_file,
+        toml::to_string(&invalid_config).unwrap(),
+    )
+    .unwrap();
+
+    assert!(matches!(
+        config_source.load(),
+        Err(ConfigError::Invalid {
+            field: "volume",
+            ..
+        })
+    ));
+    assert!(matches!(
+        Config::get_or_create_from_disk(config_dir.path(), None),
+        Err(ConfigError::Invalid {
+            field: "volume",
+            ..
+        })
+    ));
+    // Still readable to be fixed from the command line
+    assert_eq!(config_source.load_file().unwrap().volume, Some(150));
+}
+
+#[test]
+fn it_applies_the_active_profile() {
+    let (config, _) = Config::parse(&format!(
+        "active_profile = \"deep\"
+{}
+[profiles.deep]
+focus_duration = 3000
+short_break_duration = 600
+",
+        toml::to_string(&Config::default()).unwrap()
+    ))
+    .unwrap();
+
+    let effective = config.with_active_profile();
+    assert_eq!(effective.focus_duration, minutes(50));
+    assert_eq!(effective.short_break_duration, minutes(10));
+    // Settings that are not in the profile keep their value
+    assert_eq!(
+        effective.long_break_duration,
+        Config::default().long_break_duration
+    );
+
+    let config = Config {
+        active_profile: None,
+        ..config
+    };
+    assert_eq!(
+        config.with_active_profile().focus_duration,
+        Config::default().focus_duration
+    );
+}
+
+#[test]
+fn it_saves_changes_to_the_active_profile() {
+    let config = Config {
+        active_profile: Some("deep".to_string()),
+        profiles: [(
+            "deep".to_string(),
+            Profile {
+                focus_duration: Some(minutes(50)),
+                ..Profile::default()
+            },
+        )]
+        .into(),
+        ..Config::default()
+    };
+
+    let changed = Config {
+        focus_duration: minutes(60),
+        short_break_duration: minutes(10),
+        ..config.with_active_profile()
+    };
+    let config = config.apply_changes(changed);
+
+    assert_eq!(config.profiles["deep"].focus_duration, Some(minutes(60)));
+    assert_eq!(config.profiles["deep"].short_break_duration, None);
+    assert_eq!(config.focus_duration, Config::default().focus_duration);
+    assert_eq!(config.short_break_duration, minutes(10));
+}
+
+#[test]
+fn it_rejects_unknown_or_invalid_profiles() {
+    let config = Config {
+        active_profile: Some("deep".to_string()),
+        ..Config::default()
+    };
+    assert!(matches!(
+        config.validate(),
+        Err(ConfigError::Invalid {
+            field: "active_profile",
+            ..
+        })
+    ));
+
+    let config = Config {
+        profiles: [(
+            "deep".to_string(),
+            Profile {
+                max_round_number: Some(0),
+                ..Profile::default()
+            },
+        )]
+        .into(),
+        ..Config::default()
+    };
+    assert!(matches!(
+        config.validate(),
+        Err(ConfigError::Invalid {
+            field: "profiles",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn it_reads_overrides_from_the_environment_and_the_command_line() {
+    let env = Overrides::from_vars([
+        ("POMODOROLM_FOCUS_DURATION".to_string(), "1800".to_string()),
+        ("POMODOROLM_THEME".to_string(), "dracula".to_string()),
+        ("POMODOROLM_CONFIG_DIR".to_string(), "/tmp".to_string()),
+        ("HOME".to_string(), "/home/pomodorolm".to_string()),
+    ]);
+    let flags = Overrides::parse(&["focus_duration=3000".to_string()]).unwrap();
+
+    let config = env.and(flags).apply(&Config::default()).unwrap();
+    // Flags take precedence over the environment
+    assert_eq!(config.focus_duration, minutes(50));
+    assert_eq!(config.theme, "dracula");
+
+    assert!(Overrides::parse(&["focus_duration".to_string()]).is_err());
+    assert!(Overrides::parse(&["unknown_setting=1".to_string()]).is_err());
+}
+
+#[test]
+fn it_does_not_save_overridden_settings() {
+    let config_dir = tempfile::tempdir().unwrap();
+    let config_source = ConfigSource::new(
+        "pomodorolm",
+        Some(config_dir.path().to_path_buf()),
+        None,
+        Overrides::parse(&["focus_duration=3000".to_string()]).unwrap(),
+    )
+    .unwrap();
+
+    let config = config_source.load().unwrap();
+    assert_eq!(config.focus_duration, minutes(50));
+
+    config_source
+        .save(&Config {
+            short_break_duration: minutes(10),
+            ..config
+        })
+        .unwrap();
+
+    let file_config = config_source.load_file().unwrap();
+    assert_eq!(file_config.focus_duration, Config::default().focus_duration);
+    assert_eq!(file_config.short_break_duration, minutes(10));
+}
+
+#[test]
+fn it_splits_the_auto_start_of_the_breaks() {
+    // A version 1 file, with a single setting for both types of breaks
+    let mut table = toml::Table::try_from(Config::default()).unwrap();
+    table.remove("auto_start_long_break_timer");
+    table.remove("auto_start_short_break_timer");
+    table.insert("auto_start_break_timer".to_string(), false.into());
+    table.insert("version".to_string(), 1.into());
+    let (config, migrated_from) = Config::parse(&format!(
+        "{}
+[profiles.deep]
+auto_start_break_timer = true
+",
+        toml::to_string(&table).unwrap()
+    ))
+    .unwrap();
+
+    assert_eq!(migrated_from, Some(1));
+    assert!(!config.auto_start_short_break_timer);
+    assert!(!config.auto_start_long_break_timer);
+
+    let profile = &config.profiles["deep"];
+    assert_eq!(profile.auto_start_short_break_timer, Some(true));
+    assert_eq!(profile.auto_start_long_break_timer, Some(true));
+}
+
+#[test]
+fn it_reads_the_durations() {
+    // A version 2 file, with the durations in seconds
+    let mut table = toml::Table::try_from(Config::default()).unwrap();
+    table.insert("focus_duration".to_string(), 1500.into());
+    table.insert("max_session_duration".to_string(), (24 * 60 * 60).into());
+    table.insert("notification_timeout".to_string(), 30.into());
+    table.insert("version".to_string(), 2.into());
+    let (config, migrated_from) = Config::parse(&format!(
+        "{}
+[profiles.deep]
+focus_duration = 3000
+",
+        toml::to_string(&table).unwrap()
+    ))
+    .unwrap();
+
+    assert_eq!(migrated_from, Some(2));
+    assert_eq!(config.focus_duration, minutes(25));
+    assert_eq!(config.max_session_duration, minutes(24 * 60));
+    assert_eq!(config.notification_timeout, Some(Duration::from_secs(30)));
+    assert_eq!(config.profiles["deep"].focus_duration, Some(minutes(50)));
+
+    // Written back in a human-friendly way
+    let toml_str = toml::to_string(&config).unwrap();
+    assert!(toml_str.contains("focus_duration = \"25m\""));
+    assert!(toml_str.contains("max_session_duration = \"24h\""));
+    assert!(toml_str.contains("notification_timeout = \"30s\""));
+
+    let (config, _) = Config::parse(
+        &toml_str
+            .replace("\"25m\"", "\"20h30m\"")
+            .replace("auto_start_delay = \"0s\"", "auto_start_delay = 2.5"),
+    )
+    .unwrap();
+    assert_eq!(config.focus_duration, minutes(20 * 60 + 30));
+    assert_eq!(config.auto_start_delay, Duration::from_millis(2500));
+    assert!(config.validate().is_ok());
+
+    assert!(Config::parse(&toml_str.replace("\"25m\"", "-1")).is_err());
+}
@@ -0,0 +1,41 @@
+#![cfg(unix)]
+
+use pomodorolm_lib::control;
+
+#[tokio::test]
+async fn it_answers_the_messages_of_the_command_line() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let socket = data_dir.path().join("control.sock");
+
+    // Nobody is listening yet
+    assert!(control::send(&socket, "play").is_err());
+
+    let server_socket = socket.clone();
+    tokio::spawn(async move {
+        control::serve(&server_socket, |message| async move {
+            match message.as_str() {
+                "play" => Ok(()),
+                message => Err(format!("unknown message `{message}`")),
+            }
+        })
+        .await
+    });
+    while !socket.exists() {
+        tokio::task::yield_now().await;
+    }
+
+    // The client is blocking
+    let replies = tokio::task::spawn_blocking(move || {
+        (
+            control::send(&socket, "play").unwrap(),
+            control::send(&socket, "dance").unwrap(),
+        )
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(
+        replies,
+        (Ok(()), Err("unknown message `dance`".to_string()))
+    );
+}
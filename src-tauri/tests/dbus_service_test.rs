@@ -0,0 +1,138 @@
+#![cfg(target_os = "linux")]
+use futures::StreamExt;
+use pomodorolm_lib::dbus_service::{self, OBJECT_PATH, SERVICE_NAME, TimerState};
+use pomodorolm_lib::pomodoro::{self, Pomodoro};
+use std::sync::{Arc, Mutex};
+use tokio::net::UnixStream;
+use tokio::sync::watch;
+use zbus::zvariant::OwnedValue;
+use zbus::{Connection, MatchRule, MessageStream, connection};
+
+#[test]
+fn it_describes_the_timer() {
+    let mut running = pomodoro::play(&Pomodoro::default());
+    running.current_session.current_time = std::time::Duration::from_secs(100);
+
+    assert_eq!(
+        TimerState::of(&running, "Focus"),
+        TimerState {
+            session_type: pomodoro::SessionType::Focus,
+            status: pomodoro::SessionStatus::Running,
+            remaining: 1400,
+            round: 1,
+            label: "Focus".to_string(),
+        }
+    );
+}
+
+// A client connected to the service without any bus in between
+async fn connect(
+    handler: impl Fn(String) -> Result<(), String> + Send + Sync + 'static,
+    states: watch::Receiver<TimerState>,
+) -> Connection {
+    let (service, client) = UnixStream::pair().unwrap();
+    let guid = zbus::Guid::generate();
+
+    tokio::spawn(dbus_service::serve_on(
+        connection::Builder::unix_stream(service)
+            .server(guid)
+            .unwrap()
+            .p2p(),
+        move |message| std::future::ready(handler(message)),
+        states,
+    ));
+
+    connection::Builder::unix_stream(client)
+        .p2p()
+        .build()
+        .await
+        .unwrap()
+}
+
+async fn property(client: &Connection, name: &str) -> OwnedValue {
+    let reply = client
+        .call_method(
+            None::<&str>,
+            OBJECT_PATH,
+            Some("org.freedesktop.DBus.Properties"),
+            "Get",
+            &(SERVICE_NAME, name),
+        )
+        .await
+        .unwrap();
+    reply.body().deserialize().unwrap()
+}
+
+#[tokio::test]
+async fn it_controls_and_follows_the_timer() {
+    let pomodoro = Pomodoro::default();
+    let (sender, states) = watch::channel(TimerState::of(&pomodoro, "Focus"));
+    let messages = Arc::new(Mutex::new(vec![]));
+
+    let client = connect(
+        {
+            let messages = messages.clone();
+            move |message| {
+                messages.lock().unwrap().push(message.clone());
+                match message.as_str() {
+                    "quit" => Err("not now".to_string()),
+                    _ => Ok(()),
+                }
+            }
+        },
+        states,
+    )
+    .await;
+
+    for method in ["Play", "Skip", "ResetRound"] {
+        client
+            .call_method(None::<&str>, OBJECT_PATH, Some(SERVICE_NAME), method, &())
+            .await
+            .unwrap();
+    }
+    assert!(
+        client
+            .call_method(None::<&str>, OBJECT_PATH, Some(SERVICE_NAME), "Quit", &())
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        *messages.lock().unwrap(),
+        vec!["play", "skip", "reset_round", "quit"]
+    );
+
+    assert_eq!(
+        String::try_from(property(&client, "Status").await).unwrap(),
+        "NotStarted"
+    );
+    assert_eq!(
+        u64::try_from(property(&client, "Remaining").await).unwrap(),
+        1500
+    );
+
+    let rule = MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .member("StateChanged")
+        .unwrap()
+        .build();
+    let mut signals = MessageStream::for_match_rule(rule, &client, None)
+        .await
+        .unwrap();
+
+    sender
+        .send(TimerState::of(&pomodoro::play(&pomodoro), "Focus"))
+        .unwrap();
+
+    let signal = signals.next().await.unwrap().unwrap();
+    let (session_type, status, remaining, round, label): (String, String, u64, u16, String) =
+        signal.body().deserialize().unwrap();
+    assert_eq!(
+        (session_type.as_str(), status.as_str(), remaining, round),
+        ("Focus", "Running", 1500, 1)
+    );
+    assert_eq!(label, "Focus");
+    assert_eq!(
+        String::try_from(property(&client, "Status").await).unwrap(),
+        "Running"
+    );
+}
@@ -0,0 +1,95 @@
+use pomodorolm_lib::do_not_disturb::{self, DoNotDisturb, DoNotDisturbBackend, DoNotDisturbSwitch};
+use pomodorolm_lib::pomodoro::{self, Pomodoro};
+
+#[derive(Default)]
+struct FakeBackend {
+    calls: Vec<bool>,
+    // Silenced by the user
+    silenced: bool,
+}
+
+impl DoNotDisturb for FakeBackend {
+    async fn set(&mut self, silenced: bool) -> Result<(), String> {
+        self.calls.push(silenced);
+        Ok(())
+    }
+
+    async fn is_silenced(&mut self) -> Result<bool, String> {
+        Ok(self.silenced)
+    }
+}
+
+#[tokio::test]
+async fn it_silences_the_notifications_during_the_focus_sessions() {
+    let mut switch = DoNotDisturbSwitch::new(FakeBackend::default());
+
+    let not_started = Pomodoro::default();
+    switch.update(&not_started).await.unwrap();
+    assert!(switch.backend().calls.is_empty());
+
+    let focus = pomodoro::play(&not_started);
+    switch.update(&focus).await.unwrap();
+    // Only switched on the changes, not on every tick
+    switch.update(&pomodoro::tick(&focus)).await.unwrap();
+    assert_eq!(switch.backend().calls, vec![true]);
+
+    let paused = pomodoro::pause(&focus);
+    switch.update(&paused).await.unwrap();
+    switch.update(&focus).await.unwrap();
+    assert_eq!(switch.backend().calls, vec![true, false, true]);
+
+    let short_break = pomodoro::play(&pomodoro::next(&focus));
+    switch.update(&short_break).await.unwrap();
+    assert_eq!(switch.backend().calls, vec![true, false, true, false]);
+}
+
+#[tokio::test]
+async fn it_brings_the_notifications_back_when_quitting() {
+    let mut switch = DoNotDisturbSwitch::new(FakeBackend::default());
+    switch.restore().await.unwrap();
+    assert!(switch.backend().calls.is_empty());
+
+    switch
+        .update(&pomodoro::play(&Pomodoro::default()))
+        .await
+        .unwrap();
+    switch.restore().await.unwrap();
+    assert_eq!(switch.backend().calls, vec![true, false]);
+}
+
+#[tokio::test]
+async fn it_leaves_the_notifications_silenced_by_the_user() {
+    let mut switch = DoNotDisturbSwitch::new(FakeBackend {
+        silenced: true,
+        ..FakeBackend::default()
+    });
+
+    let focus = pomodoro::play(&Pomodoro::default());
+    switch.update(&focus).await.unwrap();
+    switch.update(&pomodoro::pause(&focus)).await.unwrap();
+    switch.update(&focus).await.unwrap();
+    switch.restore().await.unwrap();
+    assert!(switch.backend().calls.is_empty());
+}
+
+#[test]
+fn it_detects_the_backend_of_the_desktop() {
+    let nothing_installed = |_: &str| false;
+    assert_eq!(
+        do_not_disturb::detect("ubuntu:GNOME", nothing_installed),
+        Some(DoNotDisturbBackend::Gnome)
+    );
+    assert_eq!(
+        do_not_disturb::detect("KDE", nothing_installed),
+        Some(DoNotDisturbBackend::Freedesktop)
+    );
+    assert_eq!(
+        do_not_disturb::detect("sway", |program| program == "makoctl"),
+        Some(DoNotDisturbBackend::Mako)
+    );
+    assert_eq!(
+        do_not_disturb::detect("i3", |program| program == "dunstctl"),
+        Some(DoNotDisturbBackend::Dunst)
+    );
+    assert_eq!(do_not_disturb::detect("", nothing_installed), None);
+}
@@ -0,0 +1,59 @@
+use pomodorolm_lib::duration;
+use std::time::Duration;
+
+fn seconds(seconds: u64) -> Option<Duration> {
+    Some(Duration::from_secs(seconds))
+}
+
+#[test]
+fn it_parses_human_durations() {
+    assert_eq!(duration::parse("25m"), seconds(25 * 60));
+    assert_eq!(duration::parse("1h30m"), seconds(90 * 60));
+    assert_eq!(duration::parse("1h 30m 15s"), seconds(90 * 60 + 15));
+    assert_eq!(duration::parse("90s"), seconds(90));
+    assert_eq!(duration::parse("1.5h"), seconds(90 * 60));
+    assert_eq!(
+        duration::parse("1s500ms"),
+        Some(Duration::from_millis(1500))
+    );
+    assert_eq!(duration::parse("24h"), seconds(24 * 60 * 60));
+    // Plain numbers are seconds
+    assert_eq!(duration::parse("1500"), seconds(1500));
+    assert_eq!(duration::parse("0.25"), Some(Duration::from_millis(250)));
+}
+
+#[test]
+fn it_rejects_invalid_durations() {
+    assert_eq!(duration::parse(""), None);
+    assert_eq!(duration::parse("m"), None);
+    assert_eq!(duration::parse("25 minutes"), None);
+    assert_eq!(duration::parse("25m30"), None);
+    assert_eq!(duration::parse("-5m"), None);
+    assert_eq!(duration::parse("1..5h"), None);
+}
+
+#[test]
+fn it_formats_durations() {
+    assert_eq!(duration::format(Duration::from_secs(25 * 60)), "25m");
+    assert_eq!(
+        duration::format(Duration::from_secs(90 * 60 + 15)),
+        "1h30m15s"
+    );
+    assert_eq!(duration::format(Duration::from_secs(48 * 60 * 60)), "48h");
+    assert_eq!(duration::format(Duration::from_millis(1500)), "1s500ms");
+    assert_eq!(duration::format(Duration::ZERO), "0s");
+
+    for milliseconds in [
+        1,
+        999,
+        1000,
+        59_000,
+        60_000,
+        1_500_000,
+        5_415_250,
+        100_000_000,
+    ] {
+        let duration = Duration::from_millis(milliseconds);
+        assert_eq!(duration::parse(&duration::format(duration)), Some(duration));
+    }
+}
@@ -0,0 +1,88 @@
+use chrono::{Duration, Local};
+use pomodorolm_lib::history::{Event, History};
+use pomodorolm_lib::pomodoro::SessionType;
+use pomodorolm_lib::screen_lock::SystemEvent;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+fn completed(session_type: SessionType) -> Event {
+    Event::SessionCompleted {
+        session_type,
+        duration: 1500,
+        label: None,
+    }
+}
+
+#[test]
+fn it_counts_the_focus_sessions_of_the_day() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let history_file = data_dir.path().join("history.jsonl");
+    let history = History::new(&history_file);
+
+    assert_eq!(history.entries().unwrap(), vec![]);
+
+    history.record(completed(SessionType::Focus)).unwrap();
+    history.record(completed(SessionType::ShortBreak)).unwrap();
+    history.record(completed(SessionType::Focus)).unwrap();
+
+    // A bad line is ignored instead of making the whole history unreadable
+    let mut file = OpenOptions::new().append(true).open(&history_file).unwrap();
+    writeln!(file, "not json").unwrap();
+
+    let today = Local::now().date_naive();
+    assert_eq!(history.entries().unwrap().len(), 3);
+    assert_eq!(history.completed_focus_count(today).unwrap(), 2);
+    assert_eq!(
+        history
+            .completed_focus_count(today - Duration::days(1))
+            .unwrap(),
+        0
+    );
+}
+
+#[test]
+fn it_counts_a_reopened_focus_session_once() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let history = History::new(&data_dir.path().join("history.jsonl"));
+
+    history.record(completed(SessionType::Focus)).unwrap();
+    history
+        .record(Event::SessionExtended {
+            session_type: SessionType::Focus,
+            seconds: 300,
+            reopened: true,
+        })
+        .unwrap();
+    history.record(completed(SessionType::Focus)).unwrap();
+
+    assert_eq!(
+        history
+            .completed_focus_count(Local::now().date_naive())
+            .unwrap(),
+        1
+    );
+}
+
+#[test]
+fn it_records_why_a_session_ended() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let history_file = data_dir.path().join("history.jsonl");
+    let history = History::new(&history_file);
+
+    history
+        .record(Event::SessionEnded {
+            session_type: SessionType::Focus,
+            cause: SystemEvent::Sleep,
+        })
+        .unwrap();
+
+    let line = std::fs::read_to_string(&history_file).unwrap();
+    assert!(line.contains(r#""event":"session_ended","session_type":"Focus","cause":"sleep""#));
+    // Not counted as a completed session
+    assert_eq!(
+        history
+            .completed_focus_count(Local::now().date_naive())
+            .unwrap(),
+        0
+    );
+}
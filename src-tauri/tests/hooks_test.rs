@@ -0,0 +1,165 @@
+use pomodorolm_lib::hooks::{self, Change, HookEvent};
+use pomodorolm_lib::pomodoro::{self, Config, Pomodoro, SessionType};
+use std::time::Duration;
+
+fn hook_events(previous: &Pomodoro, current: &Pomodoro, change: Change) -> Vec<HookEvent> {
+    hooks::events(previous, current, change)
+        .into_iter()
+        .map(|data| data.event)
+        .collect()
+}
+
+#[test]
+fn it_finds_the_events_of_the_transitions() {
+    let not_started = Pomodoro::default();
+    let focus = pomodoro::play(&not_started);
+    assert_eq!(
+        hook_events(&not_started, &focus, Change::Other),
+        vec![HookEvent::FocusStart]
+    );
+    assert_eq!(
+        hook_events(&focus, &pomodoro::tick(&focus), Change::Other),
+        vec![]
+    );
+
+    let paused = pomodoro::pause(&focus);
+    assert_eq!(
+        hook_events(&focus, &paused, Change::Other),
+        vec![HookEvent::Pause]
+    );
+    assert_eq!(
+        hook_events(&paused, &focus, Change::Other),
+        vec![HookEvent::Resume]
+    );
+
+    let short_break = pomodoro::next(&focus);
+    assert_eq!(
+        hook_events(&focus, &short_break, Change::Completed),
+        vec![HookEvent::FocusEnd]
+    );
+    assert_eq!(
+        hook_events(&focus, &short_break, Change::Skipped),
+        vec![HookEvent::Skip]
+    );
+    assert_eq!(
+        hook_events(&short_break, &pomodoro::play(&short_break), Change::Other),
+        vec![HookEvent::BreakStart]
+    );
+}
+
+#[test]
+fn the_resets_should_not_end_the_session() {
+    let second_focus = pomodoro::play(&Pomodoro {
+        current_work_round_number: 2,
+        ..Pomodoro::default()
+    });
+    let reset = pomodoro::reset_session(&second_focus);
+    assert_eq!(reset.current_work_round_number, 1);
+    assert_eq!(hook_events(&second_focus, &reset, Change::Reset), vec![]);
+
+    // Undoing the reset runs the focus session again, without ending the reset one
+    assert_eq!(
+        hook_events(&reset, &second_focus, Change::Reset),
+        vec![HookEvent::FocusStart]
+    );
+
+    let short_break = pomodoro::play(&pomodoro::next(&second_focus));
+    assert_eq!(
+        hook_events(
+            &short_break,
+            &pomodoro::reset_round(&short_break),
+            Change::Reset
+        ),
+        vec![]
+    );
+}
+
+#[test]
+fn the_consecutive_sessions_of_the_same_type_should_end() {
+    let focus = pomodoro::play(&Pomodoro::default());
+    let mut next_focus = focus.clone();
+    next_focus.current_session.current_time = std::time::Duration::ZERO;
+
+    let events = hooks::events(&focus, &next_focus, Change::Completed);
+    assert_eq!(
+        events.iter().map(|data| data.event).collect::<Vec<_>>(),
+        vec![HookEvent::FocusEnd, HookEvent::FocusStart]
+    );
+    assert_eq!(events[1].session_type, SessionType::Focus);
+}
+
+#[test]
+fn it_tells_when_the_cycle_is_complete() {
+    let last_focus = pomodoro::play(&Pomodoro {
+        config: Config {
+            auto_start_long_break_timer: true,
+            ..Config::default()
+        },
+        current_work_round_number: 4,
+        ..Pomodoro::default()
+    });
+    let long_break = pomodoro::next(&last_focus);
+
+    let events = hooks::events(&last_focus, &long_break, Change::Completed);
+    assert_eq!(
+        events.iter().map(|data| data.event).collect::<Vec<_>>(),
+        vec![
+            HookEvent::FocusEnd,
+            HookEvent::CycleComplete,
+            HookEvent::BreakStart
+        ]
+    );
+    assert_eq!(events[1].round, 4);
+    assert_eq!(events[2].session_type, pomodoro::SessionType::LongBreak);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn it_runs_the_hooks_with_the_session_data() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("output");
+    let data = hooks::events(
+        &Pomodoro::default(),
+        &pomodoro::play(&Pomodoro::default()),
+        Change::Other,
+    )
+    .remove(0);
+
+    hooks::run(
+        &format!(
+            "echo \"$POMODOROLM_EVENT $POMODOROLM_SESSION_TYPE $POMODOROLM_DURATION\" > {0}; cat >> {0}",
+            output.display()
+        ),
+        &data,
+        Duration::from_secs(5),
+    )
+    .await
+    .unwrap();
+
+    let content = std::fs::read_to_string(&output).unwrap();
+    let (env, json) = content.split_once('\n').unwrap();
+    assert_eq!(env, "focus_start Focus 1500");
+    let json: serde_json::Value = serde_json::from_str(json).unwrap();
+    assert_eq!(json["event"], "focus_start");
+    assert_eq!(json["round"], 1);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn it_reports_the_failing_and_the_slow_hooks() {
+    let data = hooks::events(
+        &Pomodoro::default(),
+        &pomodoro::play(&Pomodoro::default()),
+        Change::Other,
+    )
+    .remove(0);
+
+    assert!(
+        hooks::run("exit 3", &data, Duration::from_secs(5))
+            .await
+            .is_err()
+    );
+
+    let result = hooks::run("sleep 10", &data, Duration::from_millis(100)).await;
+    assert!(result.unwrap_err().contains("killed after 100ms"));
+}
@@ -0,0 +1,221 @@
+use pomodorolm_lib::http_api;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+
+// Serves the API with a handler recording the messages, on a free port
+async fn start(
+    allowed_origins: &[&str],
+) -> (SocketAddr, Arc<Mutex<Vec<String>>>, watch::Sender<String>) {
+    let listener = http_api::bind(0).await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let messages = Arc::new(Mutex::new(vec![]));
+    let (sender, states) = watch::channel(r#"{"status":"NotStarted"}"#.to_string());
+
+    tokio::spawn(http_api::serve(
+        listener,
+        http_api::Access {
+            token: "secret".to_string(),
+            allowed_origins: allowed_origins
+                .iter()
+                .map(|origin| origin.to_string())
+                .collect(),
+        },
+        {
+            let messages = messages.clone();
+            move |message: String| {
+                let result = match message.as_str() {
+                    "skip" => Err("nothing to skip".to_string()),
+                    _ => Ok(()),
+                };
+                messages.lock().unwrap().push(message);
+                std::future::ready(result)
+            }
+        },
+        states,
+    ));
+
+    (address, messages, sender)
+}
+
+const AUTHORIZATION: &str = "Authorization: Bearer secret\r\n";
+
+// The head and the body of the response, to a request with the right host
+async fn send(address: SocketAddr, method: &str, target: &str, headers: &str) -> (String, String) {
+    let host = format!("Host: localhost:{}\r\n", address.port());
+    send_raw(address, method, target, &format!("{host}{headers}")).await
+}
+
+async fn send_raw(
+    address: SocketAddr,
+    method: &str,
+    target: &str,
+    headers: &str,
+) -> (String, String) {
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    stream
+        .write_all(
+            format!("{method} {target} HTTP/1.1\r\nConnection: close\r\n{headers}\r\n").as_bytes(),
+        )
+        .await
+        .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    (head.to_string(), body.to_string())
+}
+
+// The status line and the body of the response, to an authorized request
+async fn request(
+    address: SocketAddr,
+    method: &str,
+    target: &str,
+    headers: &str,
+) -> (String, String) {
+    let (head, body) = send(
+        address,
+        method,
+        target,
+        &format!("{AUTHORIZATION}{headers}"),
+    )
+    .await;
+    (head.lines().next().unwrap().to_string(), body)
+}
+
+#[tokio::test]
+async fn it_gives_the_status_and_controls_the_timer() {
+    let (address, messages, _sender) = start(&[]).await;
+
+    let (status, body) = request(address, "GET", "/status", "").await;
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert_eq!(body, r#"{"status":"NotStarted"}"#);
+
+    for action in ["/play", "/pause", "/reset"] {
+        let (status, _) = request(address, "POST", action, "Content-Length: 0\r\n").await;
+        assert_eq!(status, "HTTP/1.1 200 OK");
+    }
+    let (status, body) = request(address, "POST", "/skip", "Content-Length: 0\r\n").await;
+    assert_eq!(status, "HTTP/1.1 409 Conflict");
+    assert_eq!(body, "nothing to skip");
+    assert_eq!(
+        *messages.lock().unwrap(),
+        vec!["play", "pause", "reset_round", "skip"]
+    );
+
+    let (status, _) = request(address, "GET", "/play", "").await;
+    assert_eq!(status, "HTTP/1.1 405 Method Not Allowed");
+    let (status, _) = request(address, "GET", "/nothing", "").await;
+    assert_eq!(status, "HTTP/1.1 404 Not Found");
+}
+
+#[tokio::test]
+async fn it_needs_the_token() {
+    let (address, messages, _sender) = start(&[]).await;
+
+    let (head, _) = send(address, "GET", "/status", "").await;
+    assert!(head.starts_with("HTTP/1.1 401 Unauthorized"));
+    let (head, _) = send(address, "POST", "/play", "Authorization: Bearer wrong\r\n").await;
+    assert!(head.starts_with("HTTP/1.1 401 Unauthorized"));
+    let (head, _) = send(address, "POST", "/play", "Authorization: Bearer secre\r\n").await;
+    assert!(head.starts_with("HTTP/1.1 401 Unauthorized"));
+    assert!(messages.lock().unwrap().is_empty());
+
+    let (head, _) = send(address, "GET", "/status", AUTHORIZATION).await;
+    assert!(head.starts_with("HTTP/1.1 200 OK"));
+    let (head, _) = send(address, "GET", "/status?token=secret", "").await;
+    assert!(head.starts_with("HTTP/1.1 200 OK"));
+}
+
+#[tokio::test]
+async fn it_only_answers_the_requests_made_to_localhost() {
+    let (address, messages, _sender) = start(&[]).await;
+    let port = address.port();
+
+    for host in [
+        format!("Host: evil.example:{port}\r\n"),
+        "Host: localhost\r\n".to_string(),
+        String::new(),
+    ] {
+        let (head, _) = send_raw(
+            address,
+            "POST",
+            "/play",
+            &format!("{host}{AUTHORIZATION}Content-Length: 0\r\n"),
+        )
+        .await;
+        assert!(head.starts_with("HTTP/1.1 403 Forbidden"), "{host}");
+    }
+    assert!(messages.lock().unwrap().is_empty());
+
+    let (head, _) = send_raw(
+        address,
+        "GET",
+        "/status",
+        &format!("Host: 127.0.0.1:{port}\r\n{AUTHORIZATION}"),
+    )
+    .await;
+    assert!(head.starts_with("HTTP/1.1 200 OK"));
+}
+
+#[tokio::test]
+async fn it_only_lets_the_allowed_origins_read_the_answers() {
+    let (address, _messages, _sender) = start(&["http://localhost:8080"]).await;
+
+    let (head, _) = send(
+        address,
+        "GET",
+        "/status",
+        &format!("{AUTHORIZATION}Origin: https://evil.example\r\n"),
+    )
+    .await;
+    assert!(!head.to_lowercase().contains("access-control-allow-origin"));
+
+    let (head, _) = send(
+        address,
+        "OPTIONS",
+        "/play",
+        "Origin: http://localhost:8080\r\n",
+    )
+    .await;
+    assert!(head.starts_with("HTTP/1.1 204 No Content"));
+    assert!(head.contains("access-control-allow-origin: http://localhost:8080"));
+    assert!(head.contains("access-control-allow-headers: authorization"));
+}
+
+#[tokio::test]
+async fn it_streams_the_states() {
+    let (address, _messages, sender) = start(&[]).await;
+
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    stream
+        .write_all(
+            format!(
+                "GET /events?token=secret HTTP/1.1\r\nHost: localhost:{}\r\n\r\n",
+                address.port()
+            )
+            .as_bytes(),
+        )
+        .await
+        .unwrap();
+
+    let mut received = String::new();
+    let mut buffer = [0; 1024];
+    let mut sent = false;
+    while received.matches("data: ").count() < 2 {
+        let read = stream.read(&mut buffer).await.unwrap();
+        assert!(read > 0, "the stream ended");
+        received.push_str(&String::from_utf8_lossy(&buffer[..read]));
+
+        if !sent && received.contains("data: ") {
+            sender.send(r#"{"status":"Running"}"#.to_string()).unwrap();
+            sent = true;
+        }
+    }
+
+    assert!(received.contains("content-type: text/event-stream"));
+    assert!(received.contains("data: {\"status\":\"NotStarted\"}\n\n"));
+    assert!(received.contains("data: {\"status\":\"Running\"}\n\n"));
+}
@@ -0,0 +1,98 @@
+use pomodorolm_lib::idle::{IdleEvent, IdleMonitor, IdleWatcher};
+use pomodorolm_lib::pomodoro::{self, Pomodoro, SessionStatus};
+use std::time::Duration;
+
+fn minutes(minutes: u64) -> Duration {
+    Duration::from_secs(minutes * 60)
+}
+
+struct FakeMonitor(Option<Duration>);
+
+impl IdleMonitor for FakeMonitor {
+    async fn idle_time(&self) -> Option<Duration> {
+        self.0
+    }
+}
+
+fn running_focus(current_time: Duration) -> Pomodoro {
+    let mut pomodoro = pomodoro::play(&Pomodoro::default());
+    pomodoro.current_session.current_time = current_time;
+    pomodoro
+}
+
+#[test]
+fn it_pauses_the_focus_sessions_when_the_user_is_away() {
+    let mut watcher = IdleWatcher::default();
+    let running = running_focus(minutes(10));
+
+    assert_eq!(watcher.update(minutes(5), minutes(4), &running), None);
+    assert_eq!(
+        watcher.update(minutes(5), minutes(5), &running),
+        Some(IdleEvent::Away(minutes(5)))
+    );
+
+    let paused = pomodoro::pause(&running);
+    assert_eq!(watcher.update(minutes(5), minutes(8), &paused), None);
+    // The idle time counted by the session doesn't change while away
+    assert_eq!(
+        watcher.update(minutes(5), Duration::ZERO, &paused),
+        Some(IdleEvent::Back(minutes(5)))
+    );
+    assert_eq!(watcher.update(minutes(5), Duration::ZERO, &paused), None);
+}
+
+#[test]
+fn it_only_counts_the_idle_time_of_the_session() {
+    let mut watcher = IdleWatcher::default();
+    let running = running_focus(minutes(2));
+
+    assert_eq!(
+        watcher.update(minutes(5), minutes(6), &running),
+        Some(IdleEvent::Away(minutes(2)))
+    );
+}
+
+#[test]
+fn it_ignores_the_breaks_and_the_paused_sessions() {
+    let mut watcher = IdleWatcher::default();
+
+    let mut pomodoro = running_focus(minutes(10));
+    pomodoro.current_session.session_type = pomodoro::SessionType::ShortBreak;
+    assert_eq!(watcher.update(minutes(5), minutes(6), &pomodoro), None);
+
+    let paused = pomodoro::pause(&running_focus(minutes(10)));
+    assert_eq!(watcher.update(minutes(5), minutes(6), &paused), None);
+}
+
+#[test]
+fn it_forgets_the_idle_time_if_the_session_was_resumed() {
+    let mut watcher = IdleWatcher::default();
+    let running = running_focus(minutes(10));
+
+    assert!(watcher.update(minutes(5), minutes(5), &running).is_some());
+
+    // Played again by the user before any input was seen
+    let resumed = pomodoro::play(&pomodoro::pause(&running));
+    assert_eq!(resumed.current_session.status, SessionStatus::Running);
+    assert_eq!(watcher.update(minutes(5), Duration::ZERO, &resumed), None);
+    assert_eq!(watcher.update(minutes(5), Duration::ZERO, &resumed), None);
+}
+
+#[tokio::test]
+async fn it_checks_the_idle_time_of_the_monitor() {
+    let mut watcher = IdleWatcher::default();
+    let running = running_focus(minutes(10));
+
+    assert_eq!(
+        watcher
+            .check(&FakeMonitor(None), minutes(5), &running)
+            .await,
+        None
+    );
+    assert_eq!(
+        watcher
+            .check(&FakeMonitor(Some(minutes(7))), minutes(5), &running)
+            .await,
+        Some(IdleEvent::Away(minutes(7)))
+    );
+}
@@ -0,0 +1,274 @@
+use pomodorolm_lib::notification::{
+    NotificationAction, NotificationContext, NotificationEvent, NotificationTemplate,
+    NotificationTemplates, actions, countdown, render,
+};
+use pomodorolm_lib::pomodoro::SessionType;
+use std::time::Duration;
+
+fn context() -> NotificationContext {
+    NotificationContext {
+        label: "Writing".to_string(),
+        next_session: SessionType::LongBreak,
+        duration: Duration::from_secs(20 * 60),
+        round: 4,
+        today_count: 7,
+    }
+}
+
+#[test]
+fn it_renders_the_placeholders() {
+    assert_eq!(
+        render(
+            "{label} done ({round}/4, {today_count} today), {duration} {next_session} {unknown}",
+            &context()
+        ),
+        "Writing done (4/4, 7 today), 20 minutes long break {unknown}"
+    );
+
+    let template = NotificationTemplate {
+        title: "{label}".to_string(),
+        body: "{duration}".to_string(),
+    };
+    assert_eq!(
+        template.render(&NotificationContext {
+            duration: Duration::from_secs(60),
+            ..context()
+        }),
+        ("Writing".to_string(), "1 minute".to_string())
+    );
+}
+
+#[test]
+fn it_picks_the_template_of_the_transition() {
+    let templates = NotificationTemplates::default();
+
+    assert_eq!(
+        templates
+            .template(NotificationEvent::between(
+                SessionType::Focus,
+                SessionType::LongBreak
+            ))
+            .title,
+        "Cycle completed"
+    );
+    assert_eq!(
+        NotificationEvent::between(SessionType::ShortBreak, SessionType::Focus),
+        NotificationEvent::ShortBreakEnd
+    );
+    // The consecutive steps of a sequence
+    assert_eq!(
+        NotificationEvent::between(SessionType::Focus, SessionType::Focus),
+        NotificationEvent::FocusEnd
+    );
+}
+
+#[test]
+fn it_offers_the_actions_of_the_next_session() {
+    let keys = |actions: Vec<(NotificationAction, String)>| {
+        actions
+            .into_iter()
+            .map(|(action, label)| (action.key(), label))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        keys(actions(
+            SessionType::ShortBreak,
+            false,
+            Duration::from_secs(300),
+            Some(Duration::from_secs(600))
+        )),
+        vec![
+            ("play", "Start break".to_string()),
+            ("extend", "+10 min".to_string()),
+            ("snooze", "Snooze 5 min".to_string()),
+            ("skip", "Skip break".to_string())
+        ]
+    );
+    // Nothing to start or to skip when an auto-started focus round follows
+    assert_eq!(
+        keys(actions(
+            SessionType::Focus,
+            true,
+            Duration::from_secs(600),
+            None
+        )),
+        vec![("snooze", "Snooze 10 min".to_string())]
+    );
+    assert_eq!(
+        NotificationAction::from_key("skip"),
+        Some(NotificationAction::Skip)
+    );
+}
+
+#[test]
+fn it_formats_the_countdown() {
+    assert_eq!(countdown(Duration::from_secs(245)), "4:05");
+    assert_eq!(
+        countdown(Duration::from_millis(20 * 60 * 1000 + 500)),
+        "20:00"
+    );
+}
+
+#[cfg(target_os = "linux")]
+mod freedesktop {
+    use pomodorolm_lib::notification::freedesktop::{self, DESTINATION, PATH};
+    use pomodorolm_lib::notification::{DesktopNotification, NotificationAction, Urgency};
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use zbus::object_server::SignalEmitter;
+    use zbus::zvariant::OwnedValue;
+
+    // A private session bus, stopped when dropped
+    struct Bus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl Bus {
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.as_mut()?)
+                .read_line(&mut address)
+                .ok()?;
+
+            Some(Bus {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+    }
+
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Received {
+        replaces_id: u32,
+        actions: Vec<String>,
+        urgency: Option<u8>,
+        expire_timeout: i32,
+    }
+
+    // Answers every notification by choosing the action `chosen_action`
+    struct NotificationServer {
+        chosen_action: &'static str,
+        received: Arc<Mutex<Received>>,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.Notifications")]
+    impl NotificationServer {
+        #[allow(clippy::too_many_arguments)]
+        async fn notify(
+            &self,
+            #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+            _app_name: String,
+            replaces_id: u32,
+            _app_icon: String,
+            _summary: String,
+            _body: String,
+            actions: Vec<String>,
+            hints: HashMap<String, OwnedValue>,
+            expire_timeout: i32,
+        ) -> u32 {
+            *self.received.lock().unwrap() = Received {
+                replaces_id,
+                actions,
+                urgency: hints
+                    .get("urgency")
+                    .and_then(|urgency| u8::try_from(urgency).ok()),
+                expire_timeout,
+            };
+            // Another notification first, its actions must be ignored
+            let _ = Self::action_invoked(&emitter, 41, "play").await;
+            let _ = Self::action_invoked(&emitter, 42, self.chosen_action).await;
+            42
+        }
+
+        #[zbus(signal)]
+        async fn action_invoked(
+            emitter: &SignalEmitter<'_>,
+            id: u32,
+            action_key: &str,
+        ) -> zbus::Result<()>;
+    }
+
+    #[tokio::test]
+    async fn it_returns_the_chosen_action() {
+        let Some(bus) = Bus::start() else {
+            eprintln!("dbus-daemon is not available, skipping");
+            return;
+        };
+
+        let received = Arc::new(Mutex::new(Received::default()));
+        let _server = zbus::connection::Builder::address(bus.address.as_str())
+            .unwrap()
+            .name(DESTINATION)
+            .unwrap()
+            .serve_at(
+                PATH,
+                NotificationServer {
+                    chosen_action: "skip",
+                    received: received.clone(),
+                },
+            )
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let connection = zbus::connection::Builder::address(bus.address.as_str())
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let sent = freedesktop::send(
+            &connection,
+            &DesktopNotification {
+                title: "Focus round completed".to_string(),
+                body: "Start a 5 minutes short break".to_string(),
+                icon: String::new(),
+                actions: vec![
+                    (NotificationAction::Play, "Start break".to_string()),
+                    (NotificationAction::Skip, "Skip break".to_string()),
+                ],
+                urgency: Urgency::Critical,
+                timeout: Some(Duration::ZERO),
+            },
+            42,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(sent.id, 42);
+        assert_eq!(sent.action().await.unwrap(), Some(NotificationAction::Skip));
+        assert_eq!(
+            *received.lock().unwrap(),
+            Received {
+                replaces_id: 42,
+                actions: vec!["play", "Start break", "skip", "Skip break"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+                urgency: Some(2),
+                // Until dismissed
+                expire_timeout: 0,
+            }
+        );
+    }
+}
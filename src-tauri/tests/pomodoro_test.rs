@@ -1,4 +1,11 @@
-use pomodorolm_lib::pomodoro::{self, Config, Pomodoro, Session, SessionStatus, SessionType};
+use pomodorolm_lib::pomodoro::{
+    self, Config, Pomodoro, Session, SessionStatus, SessionType, Step, TICK, UndoStack,
+};
+use std::time::Duration;
+
+fn minutes(minutes: u64) -> Duration {
+    Duration::from_secs(minutes * 60)
+}
 
 #[test]
 fn it_defaults_the_way_it_should() {
@@ -36,14 +43,14 @@ fn tick_should_tick_if_started() {
 
     assert_eq!(
         new_state.current_session.current_time,
-        initial_state.current_session.current_time + 1
+        initial_state.current_session.current_time + TICK
     );
 }
 
 #[test]
 fn tick_should_return_next_session_at_end_of_turn() {
     let mut initial_state = pomodoro::play(&Pomodoro::default());
-    initial_state.current_session.current_time = initial_state.config.focus_duration - 1;
+    initial_state.current_session.current_time = initial_state.config.focus_duration - TICK;
 
     // At the end of a focus session, we should switch to a short break
     let new_state = pomodoro::tick(&initial_state);
@@ -51,7 +58,7 @@ fn tick_should_return_next_session_at_end_of_turn() {
         new_state.current_session.session_type,
         SessionType::ShortBreak
     );
-    assert_eq!(new_state.current_session.current_time, 0);
+    assert_eq!(new_state.current_session.current_time, Duration::ZERO);
     assert_eq!(new_state.current_session.status, SessionStatus::NotStarted);
     // A work round includes a Focus and a Break, so the counter should be incremented only
     // at the end of a break
@@ -63,11 +70,11 @@ fn tick_should_return_next_session_at_end_of_turn() {
     // At the end of a short break round, we should switch to a focus round and
     // increment the current_work_round_number counter
     let mut initial_state = pomodoro::play(&new_state);
-    initial_state.current_session.current_time = initial_state.config.short_break_duration - 1;
+    initial_state.current_session.current_time = initial_state.config.short_break_duration - TICK;
 
     let mut new_state = pomodoro::tick(&initial_state);
 
-    assert_eq!(new_state.current_session.current_time, 0);
+    assert_eq!(new_state.current_session.current_time, Duration::ZERO);
     assert_eq!(new_state.current_session.session_type, SessionType::Focus);
     assert_eq!(new_state.current_session.status, SessionStatus::NotStarted);
     assert_eq!(
@@ -77,11 +84,11 @@ fn tick_should_return_next_session_at_end_of_turn() {
 
     // We are at the end of the last focus session, we should switch to a long break
     new_state.current_work_round_number = new_state.config.max_focus_rounds;
-    new_state.current_session.current_time = new_state.config.focus_duration - 1;
+    new_state.current_session.current_time = new_state.config.focus_duration - TICK;
 
     let mut new_state = pomodoro::tick(&pomodoro::play(&new_state));
 
-    assert_eq!(new_state.current_session.current_time, 0);
+    assert_eq!(new_state.current_session.current_time, Duration::ZERO);
     assert_eq!(
         new_state.current_session.session_type,
         SessionType::LongBreak
@@ -93,10 +100,10 @@ fn tick_should_return_next_session_at_end_of_turn() {
     );
 
     // We are at the end of the long break, we should reset to a focus session
-    new_state.current_session.current_time = new_state.config.long_break_duration - 1;
+    new_state.current_session.current_time = new_state.config.long_break_duration - TICK;
     let new_state = pomodoro::tick(&pomodoro::play(&new_state));
 
-    assert_eq!(new_state.current_session.current_time, 0);
+    assert_eq!(new_state.current_session.current_time, Duration::ZERO);
     assert_eq!(new_state.current_session.session_type, SessionType::Focus);
     assert_eq!(new_state.current_session.status, SessionStatus::NotStarted);
     assert_eq!(new_state.current_work_round_number, 1);
@@ -109,12 +116,12 @@ fn reset_should_stop_the_current_round() {
 
     assert_eq!(
         new_state.current_session.current_time,
-        initial_state.current_session.current_time + 1
+        initial_state.current_session.current_time + TICK
     );
 
     let new_state = pomodoro::reset_round(&new_state);
 
-    assert_eq!(new_state.current_session.current_time, 0);
+    assert_eq!(new_state.current_session.current_time, Duration::ZERO);
     assert_eq!(new_state.current_session.status, SessionStatus::NotStarted);
     assert_eq!(new_state.current_session.session_type, SessionType::Focus);
 }
@@ -129,7 +136,7 @@ fn auto_start_should_run_next_state() {
         ..Default::default()
     };
     let mut initial_state = pomodoro::play(&pomodoro_with_auto_start_short_break);
-    initial_state.current_session.current_time = initial_state.config.focus_duration - 1;
+    initial_state.current_session.current_time = initial_state.config.focus_duration - TICK;
 
     // At the end of a focus session, we should switch to a short break
     // that should run automatically
@@ -150,7 +157,7 @@ fn auto_start_should_run_next_state() {
     };
 
     let mut initial_state = pomodoro::play(&pomodoro_with_auto_start_long_break);
-    initial_state.current_session.current_time = initial_state.config.focus_duration - 1;
+    initial_state.current_session.current_time = initial_state.config.focus_duration - TICK;
 
     // At the end of the 4th focus session, we should switch to a long break
     // that should run automatically
@@ -175,7 +182,7 @@ fn auto_start_should_run_next_state() {
         ..Default::default()
     };
     let mut initial_state = pomodoro::play(&pomodoro_with_auto_start_focus);
-    initial_state.current_session.current_time = initial_state.config.short_break_duration - 1;
+    initial_state.current_session.current_time = initial_state.config.short_break_duration - TICK;
 
     // At the end of a break, we should switch to a focus session
     // that should run automatically
@@ -184,3 +191,379 @@ fn auto_start_should_run_next_state() {
     assert_eq!(new_state.current_session.session_type, SessionType::Focus);
     assert_eq!(new_state.current_session.status, SessionStatus::Running);
 }
+
+#[test]
+fn extend_should_not_go_past_the_max_session_duration() {
+    let initial_state = pomodoro::play(&Pomodoro::default());
+
+    let extended = pomodoro::extend(&initial_state, minutes(5));
+    assert_eq!(extended.current_session.extra_time, minutes(5));
+    assert_eq!(
+        extended.duration_of_session(&extended.current_session),
+        minutes(30)
+    );
+
+    // 90 minutes at most
+    let extended = pomodoro::extend(&extended, minutes(120));
+    assert_eq!(
+        extended.duration_of_session(&extended.current_session),
+        extended.config.max_session_duration
+    );
+
+    // Resetting the round goes back to the configured duration
+    assert_eq!(
+        pomodoro::reset_round(&extended).current_session.extra_time,
+        Duration::ZERO
+    );
+}
+
+#[test]
+fn discard_should_take_the_time_off_the_session() {
+    let mut pomodoro = pomodoro::play(&Pomodoro::default());
+    pomodoro.current_session.current_time = minutes(10);
+
+    let new_state = pomodoro::discard(&pomodoro, minutes(4));
+    assert_eq!(new_state.current_session.current_time, minutes(6));
+
+    // It can't go below the start of the session
+    let new_state = pomodoro::discard(&pomodoro, minutes(15));
+    assert_eq!(new_state.current_session.current_time, Duration::ZERO);
+}
+
+#[test]
+fn reopen_should_run_the_ended_session_again() {
+    let mut ended = pomodoro::play(&Pomodoro::default());
+    ended.current_session.current_time = ended.config.focus_duration - TICK;
+    assert_eq!(
+        pomodoro::tick(&ended).current_session.session_type,
+        SessionType::ShortBreak
+    );
+
+    let reopened = pomodoro::reopen(&ended, minutes(1));
+    assert_eq!(reopened.current_session.session_type, SessionType::Focus);
+    assert_eq!(reopened.current_session.status, SessionStatus::Running);
+
+    // It doesn't end on the next tick anymore
+    let new_state = pomodoro::tick(&reopened);
+    assert_eq!(new_state.current_session.session_type, SessionType::Focus);
+    assert_eq!(
+        new_state.current_session.current_time,
+        ended.config.focus_duration
+    );
+}
+
+#[test]
+fn reopen_should_not_add_time_past_the_max_session_duration() {
+    let mut ended = pomodoro::play(&Pomodoro::default());
+    ended.config.max_session_duration = ended.config.focus_duration;
+    ended.current_session.current_time = ended.config.focus_duration - TICK;
+
+    let reopened = pomodoro::reopen(&ended, minutes(1));
+    assert_eq!(reopened.current_session.extra_time, Duration::ZERO);
+}
+
+#[test]
+fn undo_should_go_back_to_the_state_before_a_skip() {
+    let mut undo_stack = UndoStack::default();
+    assert!(undo_stack.undo(&Pomodoro::default()).is_none());
+
+    let mut initial_state = pomodoro::play(&Pomodoro {
+        current_work_round_number: 3,
+        ..Default::default()
+    });
+    initial_state.current_session.current_time = minutes(10);
+
+    undo_stack.push(&initial_state);
+    let skipped = pomodoro::next(&initial_state);
+    undo_stack.push(&skipped);
+    let skipped_twice = Pomodoro {
+        config: Config {
+            focus_duration: minutes(50),
+            ..Default::default()
+        },
+        ..pomodoro::next(&skipped)
+    };
+    assert_eq!(skipped_twice.current_work_round_number, 4);
+
+    let restored = undo_stack.undo(&skipped_twice).unwrap();
+    assert_eq!(restored.current_session, skipped.current_session);
+    assert_eq!(restored.current_work_round_number, 3);
+
+    let restored = undo_stack.undo(&skipped_twice).unwrap();
+    assert_eq!(restored.current_session, initial_state.current_session);
+    // The config in use is kept
+    assert_eq!(restored.config.focus_duration, minutes(50));
+    assert!(undo_stack.is_empty());
+}
+
+// Runs the current session until its end
+fn complete(pomodoro: &Pomodoro) -> Pomodoro {
+    let mut state = pomodoro::play(pomodoro);
+    state.current_session.current_time = state.duration_of_session(&state.current_session) - TICK;
+
+    pomodoro::tick(&state)
+}
+
+#[test]
+fn the_daily_target_should_not_depend_on_the_long_breaks() {
+    let mut state = Pomodoro {
+        config: Config {
+            auto_start_long_break_timer: true,
+            auto_start_short_break_timer: true,
+            auto_start_focus_timer: true,
+            daily_focus_target: Some(3),
+            max_focus_rounds: 2,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let mut breaks = vec![];
+    for _ in 0..3 {
+        assert!(!state.is_done_for_the_day());
+        state = complete(&state);
+        breaks.push(state.current_session.session_type);
+        state = complete(&state);
+    }
+
+    // A long break every 2 rounds, the target going on to the next cycle
+    assert_eq!(
+        breaks,
+        [
+            SessionType::ShortBreak,
+            SessionType::LongBreak,
+            SessionType::ShortBreak
+        ]
+    );
+    assert_eq!(state.completed_focus_rounds, 3);
+    assert!(state.is_done_for_the_day());
+    // Nothing starts by itself anymore
+    assert_eq!(state.current_session.session_type, SessionType::Focus);
+    assert_eq!(state.current_session.status, SessionStatus::NotStarted);
+
+    // Skipped sessions are not completed
+    let skipped = pomodoro::next(&pomodoro::play(&state));
+    assert_eq!(skipped.completed_focus_rounds, 3);
+    assert_eq!(skipped.current_session.status, SessionStatus::NotStarted);
+
+    let tomorrow = pomodoro::new_day(&skipped);
+    assert_eq!(tomorrow.completed_focus_rounds, 0);
+    assert_eq!(tomorrow.current_session, skipped.current_session);
+    assert_eq!(
+        complete(&tomorrow).current_session.status,
+        SessionStatus::Running
+    );
+}
+
+#[test]
+fn it_should_walk_the_custom_sequences() {
+    let step = |name: &str, session_type, duration, auto_start| Step {
+        name: name.to_string(),
+        session_type,
+        duration,
+        auto_start,
+    };
+    let state = Pomodoro::new(Config {
+        sequence: vec![
+            step("Focus", SessionType::Focus, minutes(50), false),
+            step("Break", SessionType::ShortBreak, minutes(10), true),
+            step("Focus", SessionType::Focus, minutes(50), true),
+            step("Lunch", SessionType::LongBreak, minutes(30), false),
+        ],
+        ..Default::default()
+    });
+    assert_eq!(state.current_session.label.as_deref(), Some("Focus"));
+    assert_eq!(
+        state.duration_of_session(&state.current_session),
+        minutes(50)
+    );
+
+    let state = complete(&state);
+    assert_eq!(state.current_session.label.as_deref(), Some("Break"));
+    assert_eq!(state.current_session.status, SessionStatus::Running);
+    assert_eq!(
+        state.duration_of_session(&state.current_session),
+        minutes(10)
+    );
+
+    let state = complete(&state);
+    assert_eq!(state.current_work_round_number, 2);
+
+    let state = complete(&state);
+    assert_eq!(state.current_session.label.as_deref(), Some("Lunch"));
+    assert_eq!(state.current_session.session_type, SessionType::LongBreak);
+    assert_eq!(state.current_session.status, SessionStatus::NotStarted);
+
+    // The sequence starts over
+    let state = complete(&state);
+    assert_eq!(state.current_session.step, 0);
+    assert_eq!(state.current_work_round_number, 1);
+    assert_eq!(state.completed_focus_rounds, 2);
+
+    let state = pomodoro::reset_session(&complete(&state));
+    assert_eq!(state.current_session.label.as_deref(), Some("Focus"));
+    assert_eq!(state.current_session.step, 0);
+}
+
+#[test]
+fn the_last_tick_should_end_the_consecutive_steps_of_the_same_type() {
+    let step = |name: &str, session_type| Step {
+        name: name.to_string(),
+        session_type,
+        duration: minutes(25),
+        auto_start: true,
+    };
+    let state = pomodoro::play(&Pomodoro::new(Config {
+        sequence: vec![
+            step("Write", SessionType::Focus),
+            step("Review", SessionType::Focus),
+            step("Break", SessionType::ShortBreak),
+        ],
+        ..Default::default()
+    }));
+    assert!(!pomodoro::is_last_tick(&state));
+
+    let mut last_tick = state.clone();
+    last_tick.current_session.current_time = minutes(25) - TICK;
+    assert!(pomodoro::is_last_tick(&last_tick));
+
+    let state = pomodoro::tick(&last_tick);
+    assert_eq!(state.current_session.session_type, SessionType::Focus);
+    assert_eq!(state.current_session.label.as_deref(), Some("Review"));
+    assert_eq!(state.completed_focus_rounds, 1);
+
+    // Waiting for its start delay is not the end of it
+    let mut waiting = last_tick.clone();
+    waiting.current_session.start_delay = Duration::from_secs(5);
+    assert!(!pomodoro::is_last_tick(&waiting));
+
+    let mut paused = last_tick;
+    paused.current_session.status = SessionStatus::Paused;
+    assert!(!pomodoro::is_last_tick(&paused));
+}
+
+#[test]
+fn auto_start_should_wait_for_the_delay() {
+    let state = Pomodoro {
+        config: Config {
+            auto_start_delay: Duration::from_secs(10),
+            auto_start_short_break_timer: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let state = complete(&state);
+    assert_eq!(state.current_session.session_type, SessionType::ShortBreak);
+    assert_eq!(state.current_session.status, SessionStatus::Running);
+    assert_eq!(state.current_session.start_delay, Duration::from_secs(10));
+
+    let mut waiting = state.clone();
+    for _ in 0..10 {
+        waiting = pomodoro::tick(&waiting);
+        assert_eq!(waiting.current_session.current_time, Duration::ZERO);
+    }
+    assert_eq!(waiting.current_session.start_delay, Duration::ZERO);
+    assert_eq!(pomodoro::tick(&waiting).current_session.current_time, TICK);
+
+    // Playing the session starts it right away
+    let started = pomodoro::play(&pomodoro::pause(&state));
+    assert_eq!(started.current_session.start_delay, Duration::ZERO);
+    assert_eq!(pomodoro::tick(&started).current_session.current_time, TICK);
+
+    // The next focus round doesn't start by itself, so it has nothing to wait for
+    let state = pomodoro::next(&state);
+    assert_eq!(state.current_session.status, SessionStatus::NotStarted);
+    assert_eq!(state.current_session.start_delay, Duration::ZERO);
+}
+
+#[test]
+fn sessions_should_last_any_duration() {
+    // Longer than what used to fit in the seconds of a session
+    let state = pomodoro::play(&Pomodoro::new(Config {
+        focus_duration: Duration::from_secs(20 * 60 * 60),
+        max_session_duration: Duration::from_secs(24 * 60 * 60),
+        ..Default::default()
+    }));
+    let extended = pomodoro::extend(&state, Duration::from_secs(2 * 60 * 60));
+    assert_eq!(
+        extended.duration_of_session(&extended.current_session),
+        Duration::from_secs(22 * 60 * 60)
+    );
+    assert_eq!(
+        complete(&extended).current_session.session_type,
+        SessionType::ShortBreak
+    );
+
+    // A session that isn't a whole number of seconds ends on the tick that goes past its end
+    let state = pomodoro::play(&Pomodoro::new(Config {
+        focus_duration: Duration::from_millis(1500),
+        ..Default::default()
+    }));
+    let state = pomodoro::tick(&state);
+    assert_eq!(state.current_session.session_type, SessionType::Focus);
+    assert_eq!(
+        pomodoro::tick(&state).current_session.session_type,
+        SessionType::ShortBreak
+    );
+}
+
+#[test]
+fn flow_sessions_should_count_up_until_stopped() {
+    let state = Pomodoro::new(Config {
+        auto_start_short_break_timer: true,
+        flow_break_ratio: 0.2,
+        flow_mode: true,
+        ..Default::default()
+    });
+    assert!(state.is_flow_session(&state.current_session));
+    // Nothing to stop before it started
+    assert!(pomodoro::stop(&state).is_none());
+
+    let mut state = pomodoro::play(&state);
+    // Way past `focus_duration`
+    state.current_session.current_time = minutes(50);
+    let state = pomodoro::tick(&state);
+    assert_eq!(state.current_session.session_type, SessionType::Focus);
+
+    let stopped = pomodoro::stop(&state).unwrap();
+    assert_eq!(stopped.completed_focus_rounds, 1);
+    assert_eq!(
+        stopped.current_session.session_type,
+        SessionType::ShortBreak
+    );
+    assert_eq!(stopped.current_session.status, SessionStatus::Running);
+    assert!(!stopped.is_flow_session(&stopped.current_session));
+    // A fifth of the time spent focusing
+    assert_eq!(
+        stopped.duration_of_session(&stopped.current_session),
+        (minutes(50) + TICK).mul_f32(0.2)
+    );
+    // Breaks are not stopped, they can be skipped
+    assert!(pomodoro::stop(&stopped).is_none());
+
+    // The break after it lasts its configured duration again
+    let next_break = pomodoro::next(&pomodoro::next(&stopped));
+    assert_eq!(
+        next_break.duration_of_session(&next_break.current_session),
+        next_break.config.short_break_duration
+    );
+
+    // A flow session still ends by itself at `max_session_duration`
+    let state = complete(&pomodoro::next(&stopped));
+    assert_eq!(state.current_session.session_type, SessionType::ShortBreak);
+    assert_eq!(
+        state.duration_of_session(&state.current_session),
+        (state.config.max_session_duration - TICK).mul_f32(0.2)
+    );
+}
+
+#[test]
+fn the_state_for_the_front_end_should_give_the_durations_in_seconds() {
+    let mut pomodoro = Pomodoro::default();
+    pomodoro.config.short_break_duration = Duration::from_millis(300_500);
+
+    let json = serde_json::to_value(pomodoro.to_unborrowed()).unwrap();
+    assert_eq!(json["config"]["focus_duration"], 1500);
+    assert_eq!(json["config"]["short_break_duration"], 300.5);
+}
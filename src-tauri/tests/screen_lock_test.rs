@@ -0,0 +1,131 @@
+use pomodorolm_lib::pomodoro::{self, Pomodoro, SessionStatus, SessionType};
+use pomodorolm_lib::screen_lock::{
+    LockAction, LockWatcher, Reaction, SystemEvent, SystemEventSource,
+};
+use std::collections::VecDeque;
+
+// Stands for the D-Bus signals of logind
+struct FakeSource(VecDeque<SystemEvent>);
+
+impl SystemEventSource for FakeSource {
+    async fn next_event(&mut self) -> Option<SystemEvent> {
+        self.0.pop_front()
+    }
+}
+
+// The state of the pomodoro and the reactions after all the events of `events`
+async fn follow(
+    events: &[SystemEvent],
+    action: LockAction,
+    pomodoro: Pomodoro,
+) -> (Pomodoro, Vec<Reaction>) {
+    let mut source = FakeSource(events.iter().copied().collect());
+    let mut watcher = LockWatcher::default();
+    let mut pomodoro = pomodoro;
+    let mut reactions = vec![];
+
+    while let Some(event) = source.next_event().await {
+        if let Some(reaction) = watcher.update(event, action, &pomodoro) {
+            pomodoro = match reaction {
+                Reaction::Pause => pomodoro::pause(&pomodoro),
+                Reaction::End => pomodoro::next(&pomodoro),
+                Reaction::Resume => pomodoro::play(&pomodoro),
+            };
+            reactions.push(reaction);
+        }
+    }
+
+    (pomodoro, reactions)
+}
+
+fn running_focus() -> Pomodoro {
+    pomodoro::play(&Pomodoro::default())
+}
+
+#[tokio::test]
+async fn it_pauses_the_focus_session_until_the_screen_is_unlocked() {
+    let (pomodoro, reactions) = follow(
+        &[SystemEvent::Lock, SystemEvent::Unlock],
+        LockAction::Pause,
+        running_focus(),
+    )
+    .await;
+
+    assert_eq!(reactions, vec![Reaction::Pause, Reaction::Resume]);
+    assert_eq!(pomodoro.current_session.status, SessionStatus::Running);
+}
+
+#[tokio::test]
+async fn it_only_resumes_on_the_event_matching_the_pause() {
+    // Locked before going to sleep, still locked after waking up
+    let (pomodoro, reactions) = follow(
+        &[SystemEvent::Lock, SystemEvent::Sleep, SystemEvent::Wake],
+        LockAction::Pause,
+        running_focus(),
+    )
+    .await;
+    assert_eq!(reactions, vec![Reaction::Pause]);
+    assert_eq!(pomodoro.current_session.status, SessionStatus::Paused);
+
+    let (_, reactions) = follow(
+        &[
+            SystemEvent::Lock,
+            SystemEvent::Sleep,
+            SystemEvent::Wake,
+            SystemEvent::Unlock,
+        ],
+        LockAction::Pause,
+        running_focus(),
+    )
+    .await;
+    assert_eq!(reactions, vec![Reaction::Pause, Reaction::Resume]);
+}
+
+#[tokio::test]
+async fn it_ends_the_focus_session_if_asked_to() {
+    let (pomodoro, reactions) = follow(
+        &[SystemEvent::Sleep, SystemEvent::Wake],
+        LockAction::End,
+        running_focus(),
+    )
+    .await;
+
+    assert_eq!(reactions, vec![Reaction::End]);
+    assert_eq!(
+        pomodoro.current_session.session_type,
+        SessionType::ShortBreak
+    );
+}
+
+#[tokio::test]
+async fn it_leaves_the_other_sessions_alone() {
+    let events = [SystemEvent::Lock, SystemEvent::Unlock];
+
+    let (_, reactions) = follow(&events, LockAction::Nothing, running_focus()).await;
+    assert_eq!(reactions, vec![]);
+
+    // Not started
+    let (_, reactions) = follow(&events, LockAction::Pause, Pomodoro::default()).await;
+    assert_eq!(reactions, vec![]);
+
+    let short_break = pomodoro::play(&pomodoro::next(&running_focus()));
+    let (_, reactions) = follow(&events, LockAction::Pause, short_break).await;
+    assert_eq!(reactions, vec![]);
+}
+
+#[test]
+fn it_does_not_resume_a_session_the_user_changed() {
+    let mut watcher = LockWatcher::default();
+    let running = running_focus();
+
+    assert_eq!(
+        watcher.update(SystemEvent::Lock, LockAction::Pause, &running),
+        Some(Reaction::Pause)
+    );
+    // Reset from the command line while locked
+    let reset = pomodoro::reset_session(&pomodoro::pause(&running));
+    assert_eq!(
+        watcher.update(SystemEvent::Unlock, LockAction::Pause, &reset),
+        None
+    );
+}
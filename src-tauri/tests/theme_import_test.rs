@@ -0,0 +1,177 @@
+use pomodorolm_lib::theme_import::{self, ThemeFormat};
+use std::path::Path;
+
+#[test]
+fn it_guesses_the_format_from_the_extension() {
+    assert_eq!(
+        ThemeFormat::from_path(Path::new("dracula.json")),
+        Some(ThemeFormat::Pomotroid)
+    );
+    assert_eq!(
+        ThemeFormat::from_path(Path::new("dracula.yaml")),
+        Some(ThemeFormat::Base16)
+    );
+    assert_eq!(
+        ThemeFormat::from_path(Path::new("dracula.toml")),
+        Some(ThemeFormat::Alacritty)
+    );
+    assert_eq!(
+        ThemeFormat::from_path(Path::new("dracula.conf")),
+        Some(ThemeFormat::Kitty)
+    );
+    assert_eq!(ThemeFormat::from_path(Path::new("dracula")), None);
+}
+
+#[test]
+fn it_imports_pomotroid_themes() {
+    let content = r##"{
+      "name": "Andromeda",
+      "colors": {
+        "--color-long-round": "#C74DED",
+        "--color-short-round": "#00E8C6",
+        "--color-focus-round": "#EE5D43",
+        "--color-background": "#23262E",
+        "--color-background-light": "#2e323d",
+        "--color-background-lightest": "#746f77",
+        "--color-foreground": "#d5ced9",
+        "--color-foreground-darker": "#746f77",
+        "--color-foreground-darkest": "#CD9731",
+        "--color-accent": "#FFE66D"
+      }
+    }"##;
+
+    let theme =
+        theme_import::import(content, ThemeFormat::Pomotroid, "default".to_string()).unwrap();
+
+    assert_eq!(theme.name, "Andromeda");
+    assert_eq!(theme.colors.focus_round, "#EE5D43");
+    assert_eq!(theme.colors.focus_round_middle, None);
+    // The colors are written the same way as the other imported themes
+    assert_eq!(theme.colors.background_light, "#2E323D");
+
+    let theme = theme_import::import(
+        &content.replace("#23262E", "23262e"),
+        ThemeFormat::Pomotroid,
+        "default".to_string(),
+    )
+    .unwrap();
+    assert_eq!(theme.colors.background, "#23262E");
+
+    let invalid_content = content.replace("#EE5D43", "red");
+    assert!(
+        theme_import::import(
+            &invalid_content,
+            ThemeFormat::Pomotroid,
+            "default".to_string()
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn it_imports_base16_schemes() {
+    let content = r#"
+scheme: "Default Dark"
+author: "Chris Kempson (http://chriskempson.com)"
+base00: "181818"
+base01: "282828"
+base02: "383838"
+base03: "585858"
+base04: "b8b8b8"
+base05: "d8d8d8"
+base06: "e8e8e8"
+base07: "f8f8f8"
+base08: "ab4642" # red
+base09: "dc9656"
+base0A: "f7ca88"
+base0B: "a1b56c"
+base0C: "86c1b9"
+base0D: "7cafc2"
+base0E: "ba8baf"
+base0F: "a16946"
+"#;
+
+    let theme = theme_import::import(content, ThemeFormat::Base16, "default".to_string()).unwrap();
+
+    assert_eq!(theme.name, "Default Dark");
+    assert_eq!(theme.colors.background, "#181818");
+    assert_eq!(theme.colors.focus_round, "#AB4642");
+    assert_eq!(theme.colors.short_round, "#A1B56C");
+    assert_eq!(theme.colors.long_round, "#86C1B9");
+
+    // Unquoted values, with or without a `#`, and comments
+    let theme = theme_import::import(
+        &content
+            .replace(r#"base00: "181818""#, "base00: #181818 # background")
+            .replace(r#"base0B: "a1b56c""#, "base0B: a1b56c #green")
+            .replace(
+                r#"base0C: "86c1b9""#,
+                "base0C: '86c1b9' # cyan\n# base0C: \"000000\"",
+            ),
+        ThemeFormat::Base16,
+        "default".to_string(),
+    )
+    .unwrap();
+    assert_eq!(theme.colors.background, "#181818");
+    assert_eq!(theme.colors.short_round, "#A1B56C");
+    assert_eq!(theme.colors.long_round, "#86C1B9");
+
+    let missing_color = content.replace("base08", "base42");
+    assert!(
+        theme_import::import(&missing_color, ThemeFormat::Base16, "default".to_string()).is_err()
+    );
+}
+
+#[test]
+fn it_imports_alacritty_color_files() {
+    let content = r##"
+[colors.primary]
+background = "#1d1f21"
+foreground = "#c5c8c6"
+
+[colors.normal]
+black = "#1d1f21"
+red = "#cc6666"
+green = "#b5bd68"
+yellow = "#f0c674"
+blue = "#81a2be"
+magenta = "#b294bb"
+cyan = "#8abeb7"
+white = "#c5c8c6"
+
+[colors.bright]
+black = "0x666666"
+"##;
+
+    let theme =
+        theme_import::import(content, ThemeFormat::Alacritty, "tomorrow".to_string()).unwrap();
+
+    assert_eq!(theme.name, "tomorrow");
+    assert_eq!(theme.colors.background, "#1D1F21");
+    assert_eq!(theme.colors.background_lightest, "#666666");
+    assert_eq!(theme.colors.focus_round, "#CC6666");
+    assert_eq!(theme.colors.accent, "#B294BB");
+}
+
+#[test]
+fn it_imports_kitty_color_files() {
+    let content = "
+## name: Tomorrow Night
+# A regular comment
+background #1d1f21
+foreground #c5c8c6
+color0 #1d1f21
+color1 #cc6666
+color2 #b5bd68
+color5 #b294bb
+color6 #8abeb7
+color8 #666666
+";
+
+    let theme = theme_import::import(content, ThemeFormat::Kitty, "default".to_string()).unwrap();
+
+    assert_eq!(theme.name, "Tomorrow Night");
+    assert_eq!(theme.colors.foreground, "#C5C8C6");
+    assert_eq!(theme.colors.short_round, "#B5BD68");
+    assert_eq!(theme.colors.long_round, "#8ABEB7");
+}
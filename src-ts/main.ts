@@ -6,7 +6,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 
 import { attachConsole } from "@tauri-apps/plugin-log";
-import { open } from "@tauri-apps/plugin-dialog";
+import { message as showMessage, open } from "@tauri-apps/plugin-dialog";
 import { getVersion } from "@tauri-apps/api/app";
 
 // Display logs in the webview inspector
@@ -34,15 +34,6 @@ type Message = {
   value: string | ElmConfig | SoundMessage | ElmState;
 };
 
-type Notification = {
-  body: string;
-  title: string;
-  name: string;
-  red: number;
-  green: number;
-  blue: number;
-};
-
 type RustThemeColors = {
   long_round: string;
   short_round: string;
@@ -73,10 +64,12 @@ type ThemeColors = {
 };
 
 type ElmConfig = {
+  activeProfile: string | null;
   alwaysOnTop: boolean;
   autoQuit: string | null;
-  autoStartBreakTimer: boolean;
+  autoStartLongBreakTimer: boolean;
   autoStartOnAppStartup: boolean;
+  autoStartShortBreakTimer: boolean;
   autoStartWorkTimer: boolean;
   defaultFocusLabel: string;
   defaultLongBreakLabel: string;
@@ -101,11 +94,21 @@ type ElmConfig = {
   volume: number | null;
 };
 
+type ConfigError = {
+  kind: string;
+  field: string | null;
+  message: string;
+};
+
+// The durations are numbers of seconds, see `Config::to_front_end`
 type RustConfig = {
+  active_profile: string | null;
   always_on_top: boolean;
   auto_quit: string | null;
-  auto_start_break_timer: boolean;
+  auto_start_delay: number;
+  auto_start_long_break_timer: boolean;
   auto_start_on_app_startup: boolean;
+  auto_start_short_break_timer: boolean;
   auto_start_work_timer: boolean;
   default_focus_label: string;
   default_long_break_label: string;
@@ -120,23 +123,30 @@ type RustConfig = {
   minimize_to_tray: boolean;
   minimize_to_tray_on_close: boolean;
   muted: boolean;
+  profiles?: Record<string, object>;
   short_break_audio: string | null;
   short_break_duration: number;
   start_minimized: boolean;
   system_startup_auto_start: boolean;
   theme: string;
+  theme_dark: string | null;
+  theme_light: string | null;
   tick_sounds_during_work: boolean;
   tick_sounds_during_break: boolean;
+  version: number;
   volume: number | number;
 };
 
 const root = document.querySelector("#app div");
 
 let rustConfig: RustConfig = {
+  active_profile: null,
   always_on_top: true,
   auto_quit: null,
-  auto_start_break_timer: true,
+  auto_start_delay: 0,
+  auto_start_long_break_timer: true,
   auto_start_on_app_startup: false,
+  auto_start_short_break_timer: true,
   auto_start_work_timer: true,
   default_focus_label: "Focus",
   default_long_break_label: "Long break",
@@ -156,8 +166,11 @@ let rustConfig: RustConfig = {
   start_minimized: true,
   system_startup_auto_start: false,
   theme: "pomodorolm",
+  theme_dark: null,
+  theme_light: null,
   tick_sounds_during_work: true,
   tick_sounds_during_break: true,
+  version: 3,
   volume: 100,
 };
 
@@ -169,22 +182,23 @@ app = Elm.Main.init({
     alwaysOnTop: rustConfig.always_on_top,
     appVersion: await getAppVersion(),
     autoQuit: rustConfig.auto_quit,
-    autoStartBreakTimer: rustConfig.auto_start_break_timer,
+    autoStartLongBreakTimer: rustConfig.auto_start_long_break_timer,
     autoStartOnAppStartup: rustConfig.auto_start_on_app_startup,
+    autoStartShortBreakTimer: rustConfig.auto_start_short_break_timer,
     autoStartWorkTimer: rustConfig.auto_start_work_timer,
     defaultFocusLabel: rustConfig.default_focus_label,
     defaultLongBreakLabel: rustConfig.default_long_break_label,
     defaultShortBreakLabel: rustConfig.default_short_break_label,
     desktopNotifications: rustConfig.desktop_notifications,
     focusAudio: rustConfig.focus_audio,
-    focusDuration: rustConfig.focus_duration,
-    longBreakDuration: rustConfig.long_break_duration,
+    focusDuration: Math.round(rustConfig.focus_duration),
+    longBreakDuration: Math.round(rustConfig.long_break_duration),
     maxRoundNumber: rustConfig.max_round_number,
-    maxSessionDuration: rustConfig.max_session_duration,
+    maxSessionDuration: Math.round(rustConfig.max_session_duration),
     minimizeToTray: rustConfig.minimize_to_tray,
     minimizeToTrayOnClose: rustConfig.minimize_to_tray_on_close,
     muted: rustConfig.muted,
-    shortBreakDuration: rustConfig.short_break_duration,
+    shortBreakDuration: Math.round(rustConfig.short_break_duration),
     startMinimized: rustConfig.start_minimized,
     systemStartupAutoStart: rustConfig.system_startup_auto_start,
     theme: rustConfig.theme,
@@ -206,10 +220,6 @@ app.ports.closeWindow.subscribe(function () {
   invoke("close_window");
 });
 
-app.ports.notify.subscribe(function (notification: Notification) {
-  invoke("notify", { notification: notification });
-});
-
 app.ports.sendMessageFromElm.subscribe(async function (message: Message) {
   console.log(`Sending message from Elm ${message} ${message.name}`);
   switch (message.name) {
@@ -246,15 +256,8 @@ app.ports.sendMessageFromElm.subscribe(async function (message: Message) {
       break;
 
     case "update_current_state":
+      // The tray icon itself is drawn by Rust from the pomodoro state
       let state: ElmState = message.value as ElmState;
-      invoke("change_icon", {
-        red: hexToRgb(state.color)?.r,
-        green: hexToRgb(state.color)?.g,
-        blue: hexToRgb(state.color)?.b,
-        fillPercentage: state.percentage,
-        paused: state.paused,
-      });
-
       invoke("update_session_status", { status: state.sessionStatus });
 
       break;
@@ -263,14 +266,18 @@ app.ports.sendMessageFromElm.subscribe(async function (message: Message) {
       console.log("Getting init data from Rust");
 
       invoke("load_init_data").then((init_data) => {
-        const [config, themes, pomodoroState] = init_data as [
+        const [config, themes, pomodoroState, activeTheme] = init_data as [
           RustConfig,
           Array<RustThemeColors>,
-          any
+          any,
+          string
         ];
         console.log("Got init data from Rust");
         console.log({ config, themes, pomodoroState });
+        // Keep the settings that are not editable from the UI
+        rustConfig = config;
         app.ports.sendMessageToElm.send({
+          active_theme: activeTheme,
           config,
           themes,
           pomodoro_state: pomodoroState,
@@ -284,38 +291,56 @@ app.ports.sendMessageFromElm.subscribe(async function (message: Message) {
 
       console.log(config);
 
+      const savedConfig = rustConfig;
+
+      rustConfig = {
+        ...rustConfig,
+        active_profile: config.activeProfile,
+        always_on_top: config.alwaysOnTop,
+        auto_quit: config.autoQuit,
+        auto_start_long_break_timer: config.autoStartLongBreakTimer,
+        auto_start_on_app_startup: config.autoStartOnAppStartup,
+        auto_start_short_break_timer: config.autoStartShortBreakTimer,
+        auto_start_work_timer: config.autoStartWorkTimer,
+        default_focus_label: config.defaultFocusLabel,
+        default_long_break_label: config.defaultLongBreakLabel,
+        default_short_break_label: config.defaultShortBreakLabel,
+        desktop_notifications: config.desktopNotifications,
+        focus_audio: config.focusAudio,
+        focus_duration: editedDuration(rustConfig.focus_duration, config.focusDuration),
+        long_break_audio: config.longBreakAudio,
+        long_break_duration: editedDuration(rustConfig.long_break_duration, config.longBreakDuration),
+        max_round_number: config.maxRoundNumber,
+        max_session_duration: editedDuration(rustConfig.max_session_duration, config.maxSessionDuration),
+        minimize_to_tray: config.minimizeToTray,
+        minimize_to_tray_on_close: config.minimizeToTrayOnClose,
+        muted: config.muted,
+        short_break_audio: config.shortBreakAudio,
+        short_break_duration: editedDuration(rustConfig.short_break_duration, config.shortBreakDuration),
+        start_minimized: config.startMinimized,
+        system_startup_auto_start: config.systemStartupAutoStart,
+        theme: config.theme,
+        tick_sounds_during_work: config.tickSoundsDuringWork,
+        tick_sounds_during_break: config.tickSoundsDuringBreak,
+        volume: config.volume,
+      };
+
       invoke("update_config", {
-        config: {
-          always_on_top: config.alwaysOnTop,
-          auto_quit: config.autoQuit,
-          auto_start_break_timer: config.autoStartBreakTimer,
-          auto_start_on_app_startup: config.autoStartOnAppStartup,
-          auto_start_work_timer: config.autoStartWorkTimer,
-          default_focus_label: config.defaultFocusLabel,
-          default_long_break_label: config.defaultLongBreakLabel,
-          default_short_break_label: config.defaultShortBreakLabel,
-          desktop_notifications: config.desktopNotifications,
-          focus_audio: config.focusAudio,
-          focus_duration: config.focusDuration,
-          long_break_audio: config.longBreakAudio,
-          long_break_duration: config.longBreakDuration,
-          max_round_number: config.maxRoundNumber,
-          max_session_duration: config.maxSessionDuration,
-          minimize_to_tray: config.minimizeToTray,
-          minimize_to_tray_on_close: config.minimizeToTrayOnClose,
-          muted: config.muted,
-          short_break_audio: config.shortBreakAudio,
-          short_break_duration: config.shortBreakDuration,
-          start_minimized: config.startMinimized,
-          system_startup_auto_start: config.systemStartupAutoStart,
-          theme: config.theme,
-          tick_sounds_during_work: config.tickSoundsDuringWork,
-          tick_sounds_during_break: config.tickSoundsDuringBreak,
-          volume: config.volume,
-        },
-      }).then((newState) => {
-        app.ports.sendMessageToElm.send(newState);
-      });
+        config: rustConfig,
+      })
+        .then((newState) => {
+          app.ports.sendMessageToElm.send(newState);
+        })
+        .catch((error: ConfigError) => {
+          console.error(error);
+          showMessage(error.message, {
+            title: "Unable to save the settings",
+            kind: "error",
+          });
+          // Nothing has been saved: go back to the previous config
+          rustConfig = savedConfig;
+          app.ports.sendMessageToElm.send({ config_changed: savedConfig });
+        });
       break;
 
     default:
@@ -375,6 +400,15 @@ await listen("external-message", (message) => {
   app.ports.sendMessageToElm.send(message.payload);
 });
 
+await listen("theme-changed", (message) => {
+  app.ports.sendMessageToElm.send(message.payload);
+});
+
+await listen("config-changed", (message) => {
+  rustConfig = message.payload as RustConfig;
+  app.ports.sendMessageToElm.send({ config_changed: message.payload });
+});
+
 await listen("toggle-play", () => {
   app.ports.togglePlay.send(null);
 });
@@ -383,17 +417,6 @@ await listen("skip", () => {
   app.ports.skip.send(null);
 });
 
-function hexToRgb(hex: string) {
-  var result = /^#?([a-f\d]{2})([a-f\d]{2})([a-f\d]{2})$/i.exec(hex);
-  return result
-    ? {
-        r: parseInt(result[1], 16),
-        g: parseInt(result[2], 16),
-        b: parseInt(result[3], 16),
-      }
-    : null;
-}
-
 async function getAppVersion() {
   if (window.__TAURI_INTERNALS__ === undefined) {
     return "unknown";
@@ -401,3 +424,9 @@ async function getAppVersion() {
     return await getVersion();
   }
 }
+
+// The settings only deal with whole seconds: keep the fractional part of a duration of the
+// config file as long as it isn't edited
+function editedDuration(current: number, edited: number) {
+  return Math.round(current) === edited ? current : edited;
+}